@@ -1,9 +1,11 @@
 use clap::{App, Arg};
 use crossbeam_channel::{bounded, Sender};
-use hashbrown::HashMap;
+use hashbrown::{HashMap, HashSet};
 use memmap2::Mmap;
 use rayon::prelude::*;
+use std::collections::BTreeMap;
 use std::fs::File;
+use std::fmt::Write as _;
 use std::io::{BufWriter, Write};
 use std::thread;
 
@@ -12,8 +14,20 @@ const AIS_CHAR_BITS: usize = 6;
 const BATCH_SIZE: usize = 2048;
 /// Output BufWriter buffer (64 MB)
 const WRITER_BUF_SIZE: usize = 64 * 1024 * 1024;
-/// Max AIS payload characters (type 5 is the longest, ~70 chars; 128 is safe)
-const MAX_PAYLOAD_CHARS: usize = 128;
+/// Payload characters decoded on the stack: a 5-slot message carries at most
+/// 1008 bits (168 chars), e.g. a type 12 safety message with a 156-character
+/// text. Longer payloads (multi-slot type 26, corrupt input) spill to the heap.
+const INLINE_PAYLOAD_CHARS: usize = 168;
+/// Max sentences per message: the NMEA fragment count is a single digit
+const MAX_FRAGMENTS: u8 = 9;
+/// An incomplete multi-part message is evicted once the newest reception time
+/// seen by the assembler is this many seconds past its first fragment
+const FRAGMENT_TIMEOUT_SECS: i64 = 60;
+/// Fragments received between eviction sweeps of the multiline cache
+const EVICTION_INTERVAL: u64 = 4096;
+/// The input is parsed in line-aligned runs of about this many bytes, each by
+/// one worker in input order
+const RUN_BYTES: usize = 1 << 20;
 
 // ─── PositionReport (only used for multiline caching) ───────────────────────
 
@@ -25,46 +39,178 @@ struct PositionReport {
     pub source: String,
     pub channel: String,
     pub raw_payload: String,
-    pub message_type: u64,
     pub message_class: String,
-    pub mmsi: String,
-    pub latitude: f64,
-    pub longitude: f64,
-    pub call_sign: String,
-    pub destination: String,
-    pub name: String,
-    pub ship_type: String,
-    pub eta: String,
-    pub draught: String,
-    pub imo: String,
-    pub course_over_ground: String,
-    pub position_accuracy: String,
-    pub speed_over_ground: String,
-    pub navigation_status: String,
+    pub fragment_count: u8,
+    pub fragment_number: u8,
+    /// Identifies the sentences of one multi-part message
+    pub fragment_id: String,
+    /// The input line (the first fragment's once assembled), kept for its
+    /// tag block fields and the orphan report
+    pub raw_sentence: String,
+    /// Whether the NMEA and tag block checksums matched
+    pub checksum_ok: bool,
+    /// Fill bits of the sentence (of the last fragment once assembled)
+    pub fill_bits: Option<u8>,
+}
+
+impl PositionReport {
+    /// Epoch seconds the message was received: the satellite acquisition
+    /// time when present, otherwise the landfall time
+    fn received_at(&self) -> &str {
+        if self.satellite_acquisition_time.is_empty() {
+            &self.landfall_time
+        } else {
+            &self.satellite_acquisition_time
+        }
+    }
+
+    /// Payload length in bits, without the fill bits
+    fn bit_len(&self) -> usize {
+        (self.raw_payload.len() * AIS_CHAR_BITS).saturating_sub(self.fill_bits.unwrap_or(0) as usize)
+    }
+
+    fn header(&self) -> Header<'_> {
+        Header {
+            landfall_time: &self.landfall_time,
+            group: &self.group,
+            satellite_acquisition_time: &self.satellite_acquisition_time,
+            source: &self.source,
+            channel: &self.channel,
+            raw_payload: &self.raw_payload,
+            message_class: &self.message_class,
+            checksum_ok: self.checksum_ok,
+            address: parse_sentence_address(self.raw_sentence.as_bytes()).unwrap_or(("", false)),
+            tags: TagBlock::parse(&self.raw_sentence),
+        }
+    }
 }
 
 // ─── Stack-allocated AIS payload (no heap allocation per message) ────────────
 
-struct Payload {
-    data: [u8; MAX_PAYLOAD_CHARS],
-    len: usize,
+/// 6-bit values of a payload; on the stack unless it exceeds
+/// `INLINE_PAYLOAD_CHARS`, so nothing is ever truncated
+enum Payload {
+    Inline { data: [u8; INLINE_PAYLOAD_CHARS], len: usize },
+    Spilled(Vec<u8>),
 }
 
 impl Payload {
     #[inline]
     fn from_str(s: &str) -> Self {
-        let mut p = Self { data: [0u8; MAX_PAYLOAD_CHARS], len: 0 };
-        for c in s.bytes().take(MAX_PAYLOAD_CHARS) {
-            let mut ci = c.wrapping_sub(48);
-            if ci > 40 { ci -= 8; }
-            p.data[p.len] = ci;
-            p.len += 1;
+        if s.len() > INLINE_PAYLOAD_CHARS {
+            return Payload::Spilled(s.bytes().map(dearmor).collect());
         }
-        p
+        let mut data = [0u8; INLINE_PAYLOAD_CHARS];
+        for (slot, c) in data.iter_mut().zip(s.bytes()) {
+            *slot = dearmor(c);
+        }
+        Payload::Inline { data, len: s.len() }
     }
 
     #[inline]
-    fn as_slice(&self) -> &[u8] { &self.data[..self.len] }
+    fn as_slice(&self) -> &[u8] {
+        match self {
+            Payload::Inline { data, len } => &data[..*len],
+            Payload::Spilled(data) => data,
+        }
+    }
+}
+
+/// 6-bit value of one payload character
+#[inline]
+fn dearmor(c: u8) -> u8 {
+    let ci = c.wrapping_sub(48);
+    if ci > 40 { ci - 8 } else { ci }
+}
+
+// ─── Payload validation ──────────────────────────────────────────────────────
+
+/// Why a line was not decoded; written to the reject file
+#[derive(Clone, Copy, Debug)]
+enum Reject {
+    /// The sentence or tag block checksum does not match
+    Checksum,
+    /// A payload character outside the AIS 6-bit armoring alphabet
+    PayloadCharacters,
+    /// The fill bit field is missing, not 0-5, or longer than the payload
+    FillBits,
+    /// The payload length is not one ITU-R M.1371 allows for the message type
+    PayloadLength { message_type: u64, bits: usize },
+    /// A message type ITU-R M.1371 does not define (0 or above 27)
+    MessageType { message_type: u64 },
+}
+
+/// Fill bits from the NMEA field after the payload (`<fill>*hh`)
+#[inline]
+fn extract_fill_bits(field: &str) -> Option<u8> {
+    match field.as_bytes().first() {
+        Some(&b) if (b'0'..=b'5').contains(&b) => Some(b - b'0'),
+        _ => None,
+    }
+}
+
+/// Check a payload before decoding it and return its message type and exact
+/// length in bits (characters × 6 minus the fill bits)
+#[inline]
+fn validate_payload(raw_payload: &str, pv: &[u8], fill_bits: Option<u8>) -> Result<(u64, usize), Reject> {
+    if !raw_payload.bytes().all(|c| matches!(c, b'0'..=b'W' | b'`'..=b'w')) {
+        return Err(Reject::PayloadCharacters);
+    }
+    let fill = fill_bits.ok_or(Reject::FillBits)? as usize;
+    let bits = (raw_payload.len() * AIS_CHAR_BITS).checked_sub(fill).ok_or(Reject::FillBits)?;
+    let message_type = pick_u64(pv, 0, 6);
+    if !(1..=27).contains(&message_type) {
+        Err(Reject::MessageType { message_type })
+    } else if payload_length_valid(pv, message_type, bits) {
+        Ok((message_type, bits))
+    } else {
+        Err(Reject::PayloadLength { message_type, bits })
+    }
+}
+
+/// Payload lengths in bits that ITU-R M.1371 defines for each message type
+fn payload_length_valid(pv: &[u8], message_type: u64, bits: usize) -> bool {
+    match message_type {
+        1..=4 | 9 | 11 | 18 | 22 => bits == 168,
+        // Type 5 often lacks its final 4 spare bits or is padded by up to a character
+        5 => (420..=432).contains(&bits),
+        6 => (88..=1008).contains(&bits) && application_length_valid(pv, ADDRESSED_APPLICATIONS, 72, bits),
+        7 | 13 => (72..=168).contains(&bits) && (bits - 40).is_multiple_of(32),
+        8 => (56..=1008).contains(&bits) && application_length_valid(pv, BROADCAST_APPLICATIONS, 40, bits),
+        10 => bits == 72,
+        12 => (72..=1008).contains(&bits),
+        14 => (40..=1008).contains(&bits),
+        15 => (88..=160).contains(&bits),
+        16 => bits == 96 || bits == 144,
+        17 => (80..=816).contains(&bits),
+        19 => bits == 312,
+        20 => (72..=160).contains(&bits),
+        21 => (272..=360).contains(&bits),
+        23 => bits == 160,
+        // Part A may omit its 8 spare bits
+        24 if pick_u64(pv, 38, 2) == 0 => bits == 160 || bits == 168,
+        24 => bits == 168,
+        25 => (slot_binary_header_bits(pv)..=168).contains(&bits),
+        // Type 26 ends with a 20-bit communication state
+        26 => (slot_binary_header_bits(pv) + 20..=1064).contains(&bits),
+        27 => bits == 96,
+        _ => false,
+    }
+}
+
+/// Whether a type 6/8 message is long enough for the application registered
+/// for its (DAC, FI), if any; `dac_start` is the bit offset of the DAC
+fn application_length_valid(pv: &[u8], applications: &[BinaryApplication], dac_start: usize, bits: usize) -> bool {
+    let key = (pick_u64(pv, dac_start, 10), pick_u64(pv, dac_start + 10, 6));
+    applications.iter()
+        .filter(|(registered, _, _)| *registered == key)
+        .all(|&(_, data_bits, _)| bits >= dac_start + 16 + data_bits)
+}
+
+/// Header bits of a type 25/26 message: 40, plus the destination MMSI when
+/// addressed and the DAC/FI when structured
+fn slot_binary_header_bits(pv: &[u8]) -> usize {
+    40 + 30 * pick_u64(pv, 38, 1) as usize + 16 * pick_u64(pv, 39, 1) as usize
 }
 
 // ─── Bit extraction ──────────────────────────────────────────────────────────
@@ -109,49 +255,190 @@ fn pick_string(bv: &[u8], index: usize, char_count: usize) -> String {
 
 // ─── Field extraction helpers (return &str slices, zero allocation) ──────────
 
-fn last_four_characters(text: &str) -> &str {
-    let len = text.len();
-    if len > 3 { &text[len - 4..] } else { "" }
+#[inline]
+fn extract_leading_digits(s: &str) -> &str {
+    &s[..s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len())]
 }
 
+/// Talker IDs accepted in front of VDM/VDO
+const AIS_TALKERS: [&str; 10] = ["AI", "AB", "AD", "AN", "AR", "AS", "AT", "AX", "BS", "SA"];
+
+/// Talker ID and own-ship flag of the `!<talker>VDM,` (other ships) or
+/// `!<talker>VDO,` (own ship) sentence on a line, or None for any other line
 #[inline]
-fn extract_digits_after<'a>(s: &'a str, prefix: &str) -> &'a str {
-    match s.find(prefix) {
-        Some(i) => {
-            let r = &s[i + prefix.len()..];
-            &r[..r.find(|c: char| !c.is_ascii_digit()).unwrap_or(r.len())]
-        }
-        None => "",
+fn parse_sentence_address(line: &[u8]) -> Option<(&str, bool)> {
+    let start = line.iter().rposition(|&b| b == b'!')?;
+    let address = line.get(start + 1..start + 7)?;
+    let talker = std::str::from_utf8(&address[..2]).ok()?;
+    if !AIS_TALKERS.contains(&talker) || address[5] != b',' {
+        return None;
+    }
+    match &address[2..5] {
+        b"VDM" => Some((talker, false)),
+        b"VDO" => Some((talker, true)),
+        _ => None,
     }
 }
 
+/// Fragment count, fragment number and message ID of a sentence, from the
+/// NMEA `!AIVDM,<count>,<number>,<sequential message ID>,` fields. An ORBCOMM
+/// `g:<number>-<count>-<id>` group tag fills in missing fields, and its ID
+/// replaces the single-digit sequential message ID.
 #[inline]
-fn extract_digits_dashes_after<'a>(s: &'a str, prefix: &str) -> &'a str {
-    match s.find(prefix) {
-        Some(i) => {
-            let r = &s[i + prefix.len()..];
-            &r[..r.find(|c: char| !c.is_ascii_digit() && c != '-').unwrap_or(r.len())]
+fn extract_fragment_fields<'a>(s: &'a str, group: &'a str) -> (u8, u8, &'a str) {
+    let mut tag = group.splitn(3, '-');
+    let tag_number = tag.next().and_then(|v| v.parse().ok());
+    let tag_count  = tag.next().and_then(|v| v.parse().ok());
+    let tag_id     = tag.next().unwrap_or("");
+
+    let mut fields = s[s.rfind('!').unwrap_or(0)..].split(',').skip(1);
+    let count  = fields.next().and_then(|v| v.parse().ok()).or(tag_count).unwrap_or(1);
+    let number = fields.next().and_then(|v| v.parse().ok()).or(tag_number).unwrap_or(1);
+    let seq_id = fields.next().unwrap_or("");
+    (count, number, if tag_id.is_empty() { seq_id } else { tag_id })
+}
+
+// ─── NMEA 4.x tag blocks ─────────────────────────────────────────────────────
+
+/// Fields of the tag blocks (`\key:value,...*hh\`) in front of a sentence,
+/// borrowed from the input line. Several consecutive tag blocks are combined.
+#[derive(Default, Clone)]
+struct TagBlock<'a> {
+    /// `c:` UNIX time, in seconds or milliseconds
+    unix_time: &'a str,
+    /// `d:` destination identification
+    destination: &'a str,
+    /// `g:` sentence grouping, `<number>-<count>-<id>`
+    group: &'a str,
+    /// `n:` line count
+    line_count: &'a str,
+    /// `r:` relative time
+    relative_time: &'a str,
+    /// `s:` source / station identification
+    source: &'a str,
+    /// `t:` text string
+    text: &'a str,
+    /// `i:` information string
+    info: &'a str,
+    /// Keys that are not standard tag block fields
+    other: BTreeMap<&'a str, &'a str>,
+}
+
+impl<'a> TagBlock<'a> {
+    /// Parse every tag block between the start of `line` and its `!` sentence.
+    /// Does not allocate unless a non-standard key is present.
+    fn parse(line: &'a str) -> TagBlock<'a> {
+        let mut tags = TagBlock::default();
+        let end = line.rfind('!').unwrap_or(0);
+        for block in line[..end].split('\\').skip(1).step_by(2) {
+            let body = block.rsplit_once('*').map_or(block, |(body, _)| body);
+            for (key, value) in body.split(',').filter_map(|field| field.split_once(':')) {
+                match key {
+                    "c" => tags.unix_time = value,
+                    "d" => tags.destination = value,
+                    "g" => tags.group = value,
+                    "n" => tags.line_count = value,
+                    "r" => tags.relative_time = value,
+                    "s" => tags.source = value,
+                    "t" => tags.text = value,
+                    "i" => tags.info = value,
+                    _ => { tags.other.insert(key, value); }
+                }
+            }
         }
-        None => "",
+        tags
     }
+
+    /// `c:` time as whole epoch seconds (digits only). Values of 12 digits or
+    /// more are milliseconds, which are truncated to seconds.
+    fn unix_seconds(&self) -> &'a str {
+        let digits = extract_leading_digits(self.unix_time);
+        if digits.len() >= 12 { &digits[..digits.len() - 3] } else { digits }
+    }
+}
+
+// ─── NMEA checksums ──────────────────────────────────────────────────────────
+
+/// What to do with a line whose sentence or tag block checksum does not match
+#[derive(Clone, Copy, Default, PartialEq, Eq)]
+enum ChecksumPolicy {
+    /// Skip the line
+    #[default]
+    Drop,
+    /// Decode it anyway and mark the record `"checksum_ok":false`
+    Flag,
+    /// Write it to the reject file instead of decoding it
+    Reject,
 }
 
+/// Check `body*hh`: the XOR of all body bytes must equal the hex value `hh`
 #[inline]
-fn extract_word_after<'a>(s: &'a str, prefix: &str) -> &'a str {
-    match s.find(prefix) {
-        Some(i) => {
-            let r = &s[i + prefix.len()..];
-            &r[..r
-                .find(|c: char| !c.is_ascii_alphanumeric() && c != '-' && c != '_')
-                .unwrap_or(r.len())]
-        }
-        None => "",
-    }
+fn checksum_matches(s: &str) -> bool {
+    let Some((body, rest)) = s.rsplit_once('*') else { return false };
+    let computed = body.bytes().fold(0u8, |acc, b| acc ^ b);
+    rest.get(..2)
+        .filter(|hex| hex.bytes().all(|c| c.is_ascii_hexdigit()))
+        .and_then(|hex| u8::from_str_radix(hex, 16).ok()) == Some(computed)
 }
 
+/// Verify the checksum of the NMEA sentence (`!...*hh`) and of every tag
+/// block (`\...*hh\`) in front of it
 #[inline]
-fn extract_leading_digits(s: &str) -> &str {
-    &s[..s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len())]
+fn line_checksums_ok(line: &str) -> bool {
+    let Some(start) = line.rfind('!') else { return false };
+    checksum_matches(&line[start + 1..])
+        && line[..start].split('\\').skip(1).step_by(2).all(checksum_matches)
+}
+
+// ─── Calendar helpers (proleptic Gregorian, UTC) ─────────────────────────────
+
+/// Days since 1970-01-01 for a civil date (H. Hinnant's `days_from_civil`)
+fn days_from_civil(year: i64, month: u64, day: u64) -> i64 {
+    let y = if month <= 2 { year - 1 } else { year };
+    let era = y.div_euclid(400);
+    let yoe = y - era * 400;
+    let m = month as i64;
+    let doy = (153 * (if m > 2 { m - 3 } else { m + 9 }) + 2) / 5 + day as i64 - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146_097 + doe - 719_468
+}
+
+/// Civil (year, month, day) for a count of days since 1970-01-01
+fn civil_from_days(days: i64) -> (i64, u64, u64) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u64;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u64;
+    (yoe + era * 400 + i64::from(month <= 2), month, day)
+}
+
+fn days_in_month(year: i64, month: u64) -> u64 {
+    match month {
+        2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+/// Resolve a yearless AIS ETA (month, day, hour, minute) against the epoch
+/// second it was received. The year is whichever of the previous, current or
+/// next year puts the ETA closest to reception, so a December report of a
+/// January ETA rolls over. Returns epoch seconds, or None if the ETA is
+/// incomplete or not a valid date.
+fn resolve_eta(received_at: i64, month: u64, day: u64, hour: u64, minute: u64) -> Option<i64> {
+    if !(1..=12).contains(&month) || day == 0 || hour > 23 || minute > 59 {
+        return None;
+    }
+    let (year, _, _) = civil_from_days(received_at.div_euclid(86_400));
+    (year - 1..=year + 1)
+        .filter(|&y| day <= days_in_month(y, month))
+        .map(|y| days_from_civil(y, month, day) * 86_400 + (hour * 3600 + minute * 60) as i64)
+        .min_by_key(|eta| (eta - received_at).abs())
 }
 
 // ─── JSON writing primitives (all write into an existing String, no allocs) ──
@@ -178,6 +465,21 @@ fn push_u64_str(out: &mut String, n: u64) {
     out.push('"');
 }
 
+/// Write an i64 as decimal digits
+#[inline]
+fn push_i64(out: &mut String, n: i64) {
+    if n < 0 { out.push('-'); }
+    push_u64(out, n.unsigned_abs());
+}
+
+/// Write an i64 as a quoted JSON string value  ("-12")
+#[inline]
+fn push_i64_str(out: &mut String, n: i64) {
+    out.push('"');
+    push_i64(out, n);
+    out.push('"');
+}
+
 /// Write a string value that never needs JSON escaping
 #[inline]
 fn push_safe_str(out: &mut String, s: &str) {
@@ -186,7 +488,8 @@ fn push_safe_str(out: &mut String, s: &str) {
     out.push('"');
 }
 
-/// Write a string that may contain `"` or `\` (AIS name/callsign/destination)
+/// Write a string that may contain `"`, `\` or control characters
+/// (AIS name/callsign/destination, tag block values)
 #[inline]
 fn push_escaped_str(out: &mut String, s: &str) {
     out.push('"');
@@ -194,6 +497,7 @@ fn push_escaped_str(out: &mut String, s: &str) {
         match c {
             '"' => out.push_str("\\\""),
             '\\' => out.push_str("\\\\"),
+            c if c < ' ' => { let _ = write!(out, "\\u{:04x}", c as u32); }
             c => out.push(c),
         }
     }
@@ -207,266 +511,1287 @@ fn push_f64(out: &mut String, v: f64) {
     out.push_str(buf.format_finite(v));
 }
 
+/// Write a f64 as a JSON number, or `null` when the value is not available
+#[inline]
+fn push_opt_f64(out: &mut String, v: Option<f64>) {
+    match v {
+        Some(v) => push_f64(out, v),
+        None => out.push_str("null"),
+    }
+}
+
+/// Write epoch seconds as a quoted ISO-8601 UTC timestamp, or `null`
+#[inline]
+fn push_opt_iso8601(out: &mut String, secs: Option<i64>) {
+    match secs {
+        Some(secs) => {
+            let (year, month, day) = civil_from_days(secs.div_euclid(86_400));
+            let tod = secs.rem_euclid(86_400);
+            let _ = write!(
+                out,
+                "\"{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z\"",
+                year, month, day, tod / 3600, tod % 3600 / 60, tod % 60
+            );
+        }
+        None => out.push_str("null"),
+    }
+}
+
+/// Write `len` payload bits starting at `index` as a quoted hex string;
+/// a trailing partial nibble is padded with zero bits
+#[inline]
+fn push_bits_hex(out: &mut String, bv: &[u8], index: usize, len: usize) {
+    const HEX: &[u8; 16] = b"0123456789abcdef";
+    out.push('"');
+    for pos in (index..index + len).step_by(4) {
+        let n = (index + len - pos).min(4);
+        let nibble = pick_u64(bv, pos, n) << (4 - n);
+        out.push(HEX[nibble as usize] as char);
+    }
+    out.push('"');
+}
+
+// ─── Output format ───────────────────────────────────────────────────────────
+
+/// JSON record layout
+#[derive(Clone, Copy, Default, PartialEq, Eq)]
+enum Schema {
+    /// Original layout: every record carries the same columns, integers and
+    /// flags as quoted strings, columns a message type lacks as "" or 0.0
+    #[default]
+    Legacy,
+    /// Versioned typed layout (`"schema_version":2`): JSON numbers, booleans
+    /// and null, and only the fields the message type carries
+    V2,
+}
+
+/// How decoded values are written
+#[derive(Clone, Copy, Default)]
+struct OutputFormat {
+    /// Kinematic fields in physical units (knots, degrees, °/min) with every
+    /// "not available" code written as null, instead of raw field values
+    physical_units: bool,
+    schema: Schema,
+    /// Write `checksum_ok` in every record (the `flag` checksum policy)
+    checksum_field: bool,
+}
+
+/// Columns without a value in most message types (legacy layout only)
+const STATIC_COLUMNS: &[&str] = &["call_sign", "destination", "name", "ship_type", "eta", "draught", "imo"];
+const KINEMATIC_COLUMNS: &[&str] = &["course_over_ground", "position_accuracy", "speed_over_ground", "navigation_status"];
+
+/// Appends `,"name":value` fields to a record in the selected output format.
+/// Borrows the output buffer, so the fast path stays allocation-free.
+struct JsonFields<'a> {
+    out: &'a mut String,
+    format: OutputFormat,
+}
+
+impl<'a> JsonFields<'a> {
+    #[inline]
+    fn legacy(&self) -> bool { self.format.schema == Schema::Legacy }
+
+    #[inline]
+    fn key(&mut self, name: &str) {
+        self.out.push_str(",\"");
+        self.out.push_str(name);
+        self.out.push_str("\":");
+    }
+
+    /// Integer field (quoted in the legacy layout)
+    #[inline]
+    fn uint(&mut self, name: &str, v: u64) {
+        self.key(name);
+        if self.legacy() { push_u64_str(self.out, v) } else { push_u64(self.out, v) }
+    }
+
+    #[inline]
+    fn int(&mut self, name: &str, v: i64) {
+        self.key(name);
+        if self.legacy() { push_i64_str(self.out, v) } else { push_i64(self.out, v) }
+    }
+
+    /// Integer field that may be unavailable: "" in the legacy layout, null otherwise
+    #[inline]
+    fn opt_uint(&mut self, name: &str, v: Option<u64>) {
+        match v {
+            Some(v) => self.uint(name, v),
+            None => self.unavailable(name),
+        }
+    }
+
+    /// One-bit flag: "0"/"1" in the legacy layout, false/true otherwise
+    #[inline]
+    fn flag(&mut self, name: &str, v: u64) {
+        if self.legacy() {
+            self.uint(name, v);
+        } else {
+            self.key(name);
+            self.out.push_str(if v != 0 { "true" } else { "false" });
+        }
+    }
+
+    #[inline]
+    fn opt_flag(&mut self, name: &str, v: Option<u64>) {
+        match v {
+            Some(v) => self.flag(name, v),
+            None => self.unavailable(name),
+        }
+    }
+
+    /// A field whose value is not available
+    #[inline]
+    fn unavailable(&mut self, name: &str) {
+        self.key(name);
+        self.out.push_str(if self.legacy() { "\"\"" } else { "null" });
+    }
+
+    #[inline]
+    fn float(&mut self, name: &str, v: f64) {
+        self.key(name);
+        push_f64(self.out, v);
+    }
+
+    #[inline]
+    fn opt_float(&mut self, name: &str, v: Option<f64>) {
+        self.key(name);
+        push_opt_f64(self.out, v);
+    }
+
+    /// Decimal value of a column the legacy layout writes as a string: quoted
+    /// there ("" when not available), a number or null otherwise
+    #[inline]
+    fn opt_decimal(&mut self, name: &str, v: Option<f64>) {
+        if !self.legacy() {
+            return self.opt_float(name, v);
+        }
+        self.key(name);
+        self.out.push('"');
+        if let Some(v) = v {
+            push_f64(self.out, v);
+        }
+        self.out.push('"');
+    }
+
+    /// Decoded AIS text (may contain `"` or `\`)
+    #[inline]
+    fn text(&mut self, name: &str, s: &str) {
+        self.key(name);
+        push_escaped_str(self.out, s);
+    }
+
+    /// Epoch seconds as an ISO-8601 UTC timestamp, or null ("" in the legacy
+    /// layout, where the column is a string)
+    #[inline]
+    fn timestamp(&mut self, name: &str, secs: Option<i64>) {
+        self.key(name);
+        if secs.is_none() && self.legacy() {
+            self.out.push_str("\"\"");
+        } else {
+            push_opt_iso8601(self.out, secs);
+        }
+    }
+
+    #[inline]
+    fn hex(&mut self, name: &str, bv: &[u8], index: usize, len: usize) {
+        self.key(name);
+        push_bits_hex(self.out, bv, index, len);
+    }
+
+    /// Latitude/longitude stored in 1/`divisor` degrees. With physical units,
+    /// anything beyond ±`max_degrees` (the 91°/181° "not available" codes) is null.
+    #[inline]
+    fn coordinate(&mut self, name: &str, raw: i64, divisor: f64, max_degrees: i64) {
+        if self.format.physical_units && raw.abs() > max_degrees * divisor as i64 {
+            self.opt_float(name, None);
+        } else {
+            self.float(name, raw as f64 / divisor);
+        }
+    }
+
+    /// Speed, course, heading or altitude: the raw value, or with physical
+    /// units `raw / divisor` with null from `not_available` up
+    #[inline]
+    fn kinematic(&mut self, name: &str, raw: u64, not_available: u64, divisor: f64) {
+        if self.format.physical_units {
+            self.opt_float(name, scaled(raw, not_available, divisor, 0.0));
+        } else {
+            self.uint(name, raw);
+        }
+    }
+
+    /// Columns this message type does not carry: "" in the legacy layout,
+    /// omitted otherwise
+    #[inline]
+    fn blank(&mut self, names: &[&str]) {
+        if self.legacy() {
+            for name in names {
+                self.key(name);
+                self.out.push_str("\"\"");
+            }
+        }
+    }
+
+    /// Latitude/longitude for messages without a position (0.0 in the legacy layout)
+    #[inline]
+    fn blank_position(&mut self) {
+        if self.legacy() {
+            self.out.push_str(",\"latitude\":0.0,\"longitude\":0.0");
+        }
+    }
+
+    #[inline]
+    fn end(self) { self.out.push('}'); }
+}
+
+/// Header fields common to every record
+struct Header<'a> {
+    landfall_time: &'a str,
+    group: &'a str,
+    satellite_acquisition_time: &'a str,
+    source: &'a str,
+    channel: &'a str,
+    raw_payload: &'a str,
+    message_class: &'a str,
+    checksum_ok: bool,
+    /// Talker ID and own-ship flag (VDO rather than VDM)
+    address: (&'a str, bool),
+    /// Tag block fields beyond time, group and source
+    tags: TagBlock<'a>,
+}
+
+/// Open a record and write its header. In the typed layout the epoch-second
+/// times are numbers and empty header fields are omitted.
+fn begin_record<'a>(
+    out: &'a mut String,
+    header: &Header,
+    message_type: u64,
+    format: OutputFormat,
+) -> JsonFields<'a> {
+    match format.schema {
+        Schema::Legacy => {
+            out.push_str("{\"landfall_time\":");
+            push_safe_str(out, header.landfall_time);
+            out.push_str(",\"group\":");
+            push_escaped_str(out, header.group);
+            out.push_str(",\"satellite_acquisition_time\":");
+            push_safe_str(out, header.satellite_acquisition_time);
+            out.push_str(",\"source\":");
+            push_escaped_str(out, header.source);
+            out.push_str(",\"channel\":");
+            push_escaped_str(out, header.channel);
+            out.push_str(",\"raw_payload\":");
+            push_escaped_str(out, header.raw_payload);
+        }
+        Schema::V2 => {
+            out.push_str("{\"schema_version\":2");
+            for (name, digits) in [
+                ("landfall_time", header.landfall_time),
+                ("satellite_acquisition_time", header.satellite_acquisition_time),
+            ] {
+                // Leading zeros would not be valid JSON; out-of-range times are omitted
+                if let Ok(seconds) = digits.parse::<u64>() {
+                    out.push_str(",\"");
+                    out.push_str(name);
+                    out.push_str("\":");
+                    push_u64(out, seconds);
+                }
+            }
+            for (name, value) in [
+                ("group", header.group),
+                ("source", header.source),
+                ("channel", header.channel),
+                ("raw_payload", header.raw_payload),
+            ] {
+                if !value.is_empty() {
+                    out.push_str(",\"");
+                    out.push_str(name);
+                    out.push_str("\":");
+                    push_escaped_str(out, value);
+                }
+            }
+        }
+    }
+    out.push_str(",\"message_type\":");
+    push_u64(out, message_type);
+    out.push_str(",\"message_class\":");
+    push_safe_str(out, header.message_class);
+    let (talker, own_ship) = header.address;
+    match format.schema {
+        Schema::Legacy if own_ship => out.push_str(",\"own_ship\":true"),
+        Schema::Legacy => {}
+        Schema::V2 => {
+            out.push_str(",\"talker_id\":");
+            push_escaped_str(out, talker);
+            out.push_str(if own_ship { ",\"own_ship\":true" } else { ",\"own_ship\":false" });
+        }
+    }
+    if format.checksum_field {
+        out.push_str(if header.checksum_ok { ",\"checksum_ok\":true" } else { ",\"checksum_ok\":false" });
+    }
+    let mut w = JsonFields { out, format };
+    let tags = &header.tags;
+    for (name, value) in [
+        ("tag_destination", tags.destination),
+        ("tag_text", tags.text),
+        ("tag_info", tags.info),
+    ] {
+        if !value.is_empty() {
+            w.text(name, value);
+        }
+    }
+    for (name, value) in [("tag_line_count", tags.line_count), ("tag_relative_time", tags.relative_time)] {
+        match value.parse() {
+            Ok(n) => w.uint(name, n),
+            Err(_) if !value.is_empty() => w.text(name, value),
+            Err(_) => {}
+        }
+    }
+    if !tags.other.is_empty() {
+        w.key("tag_other");
+        w.out.push('{');
+        for (i, (key, value)) in tags.other.iter().enumerate() {
+            if i > 0 { w.out.push(','); }
+            push_escaped_str(w.out, key);
+            w.out.push(':');
+            push_escaped_str(w.out, value);
+        }
+        w.out.push('}');
+    }
+    w
+}
+
 // ─── Direct-to-buffer JSON for single-line messages (zero intermediate allocs) ─
 
 /// Parse a single-line AIS sentence and append its JSON representation to `out`.
 /// For common message types (1,2,3,18) this path has zero heap allocations.
-fn append_single_line_json(sentence: &str, out: &mut String) {
+/// Returns the decoded message type, or why the payload cannot be decoded.
+fn append_single_line_json(
+    sentence: &str,
+    address: (&str, bool),
+    tags: &TagBlock,
+    checksum_ok: bool,
+    format: OutputFormat,
+    out: &mut String,
+) -> Result<u64, Reject> {
     // Split NMEA fields from the right (no Vec allocation)
     let mut parts = sentence.rsplitn(4, ',');
-    parts.next(); // skip fillbits*checksum
+    let fill_bits = extract_fill_bits(parts.next().unwrap_or(""));
     let raw_payload = parts.next().unwrap_or("");
     let channel = parts.next().unwrap_or("");
 
     let landfall_time = extract_leading_digits(sentence);
-    let sat_time = extract_digits_after(sentence, "c:");
-    let source = extract_word_after(sentence, "s:");
+    let sat_time = tags.unix_seconds();
 
-    // Decode payload using stack buffer
+    // Decode payload using stack buffer; nothing is written for an invalid one
     let pl = Payload::from_str(raw_payload);
     let pv = pl.as_slice();
-    let message_type = pick_u64(pv, 0, 6);
+    let (message_type, bits) = validate_payload(raw_payload, pv, fill_bits)?;
 
     // Write header fields (common to all message types)
-    out.push_str("{\"landfall_time\":");
-    push_safe_str(out, landfall_time);
-    out.push_str(",\"group\":\"\",\"satellite_acquisition_time\":");
-    push_safe_str(out, sat_time);
-    out.push_str(",\"source\":");
-    push_safe_str(out, source);
-    out.push_str(",\"channel\":");
-    push_safe_str(out, channel);
-    out.push_str(",\"raw_payload\":");
-    push_safe_str(out, raw_payload);
-    out.push_str(",\"message_type\":");
-    push_u64(out, message_type);
-    out.push_str(",\"message_class\":\"singleline\",\"mmsi\":");
+    let header = Header {
+        landfall_time,
+        group: tags.group,
+        satellite_acquisition_time: sat_time,
+        source: tags.source,
+        channel,
+        raw_payload,
+        message_class: "singleline",
+        checksum_ok,
+        address,
+        tags: tags.clone(),
+    };
+    let mut w = begin_record(out, &header, message_type, format);
+
+    let received_at = if sat_time.is_empty() { landfall_time } else { sat_time };
+    append_message_fields(pv, bits, message_type, received_at, &mut w);
+    w.end();
+    Ok(message_type)
+}
+
+// ─── Binary message application registry (types 6 and 8) ────────────────────
+
+/// Decodes one binary application. `pv` is the whole payload and `start` the
+/// bit offset of the application data; fields are appended through `w`.
+type BinaryDecoder = fn(pv: &[u8], start: usize, w: &mut JsonFields);
+
+/// A registered application: (DAC, FI), the length in bits of its application
+/// data (after the FI) and its decoder
+type BinaryApplication = ((u64, u64), usize, BinaryDecoder);
+
+/// Application data bits of IMO SN.1/Circ.289 met/hydro data (DAC 1, FI 31)
+const MET_HYDRO_BITS: usize = 296;
+/// Application data bits of inland ship static and voyage data (DAC 200, FI 10)
+const INLAND_STATIC_BITS: usize = 112;
+
+/// Application decoders for addressed binary messages (type 6), keyed by (DAC, FI).
+/// Regional payloads are added here without touching the message decoding.
+static ADDRESSED_APPLICATIONS: &[BinaryApplication] = &[];
+
+/// Application decoders for broadcast binary messages (type 8), keyed by (DAC, FI).
+static BROADCAST_APPLICATIONS: &[BinaryApplication] = &[
+    ((1, 31), MET_HYDRO_BITS, append_met_hydro_fields),
+    ((200, 10), INLAND_STATIC_BITS, append_inland_static_fields),
+];
+
+/// The decoder registered for (DAC, FI), unless the application data is too
+/// short for it: bits past the end of the payload would decode as zeros
+fn find_application(applications: &[BinaryApplication], dac: u64, fi: u64, data_bits: usize) -> Option<BinaryDecoder> {
+    applications.iter()
+        .find(|(key, _, _)| *key == (dac, fi))
+        .and_then(|&(_, min_bits, decode)| (data_bits >= min_bits).then_some(decode))
+}
 
+/// Convert a raw field to physical units as `(raw + offset) / divisor`,
+/// or None at/above its "not available" code
+#[inline]
+fn scaled(raw: u64, not_available: u64, divisor: f64, offset: f64) -> Option<f64> {
+    (raw < not_available).then_some((raw as f64 + offset) / divisor)
+}
+
+/// Inland AIS ship static and voyage related data (DAC 200, FI 10).
+/// Length and beam are those of the ship or, when in convoy, the whole convoy.
+fn append_inland_static_fields(pv: &[u8], start: usize, w: &mut JsonFields) {
+    let u = |offset: usize, len: usize| pick_u64(pv, start + offset, len);
+    let eni = pick_string(pv, start, 8);
+
+    w.text("eni", &eni);
+    w.opt_decimal("convoy_length", (u(48, 13) != 0).then(|| u(48, 13) as f64 / 10.0));
+    w.opt_decimal("convoy_beam", (u(61, 10) != 0).then(|| u(61, 10) as f64 / 10.0));
+    w.uint("eri_ship_type", u(71, 14));
+    w.opt_uint("hazardous_cargo", (u(85, 3) < 5).then(|| u(85, 3)));
+    w.opt_decimal("inland_draught", (u(88, 11) != 0).then(|| u(88, 11) as f64 / 100.0));
+    // Reported as 1 = loaded, 2 = unloaded; written as a flag
+    w.opt_flag("loaded", match u(99, 2) { 1 => Some(1), 2 => Some(0), _ => None });
+    w.flag("speed_quality", u(101, 1));
+    w.flag("course_quality", u(102, 1));
+    w.flag("heading_quality", u(103, 1));
+}
+
+/// IMO SN.1/Circ.289 meteorological and hydrographic data (DAC 1, FI 31).
+/// Every "not available" code is written as null ("" in the legacy layout).
+fn append_met_hydro_fields(pv: &[u8], start: usize, w: &mut JsonFields) {
+    let u = |offset: usize, len: usize| pick_u64(pv, start + offset, len);
+    let i = |offset: usize, len: usize| pick_i64(pv, start + offset, len);
+    let code = |offset: usize, len: usize, not_available: u64| {
+        let raw = u(offset, len);
+        (raw < not_available).then_some(raw)
+    };
+
+    let lon = i(0, 25);
+    let lat = i(25, 24);
+    let air_temperature = i(98, 11);
+    let dew_point = i(116, 10);
+    let water_temperature = i(270, 10);
+
+    w.opt_decimal("station_latitude", (lat != 91 * 60_000).then(|| lat as f64 / 60_000.0));
+    w.opt_decimal("station_longitude", (lon != 181 * 60_000).then(|| lon as f64 / 60_000.0));
+    w.flag("station_position_accuracy", u(49, 1));
+    w.opt_uint("observation_day", code(50, 5, 32).filter(|&d| d != 0));
+    w.opt_uint("observation_hour", code(55, 5, 24));
+    w.opt_uint("observation_minute", code(60, 6, 60));
+    w.opt_decimal("wind_speed", scaled(u(66, 7), 127, 1.0, 0.0));
+    w.opt_decimal("wind_gust", scaled(u(73, 7), 127, 1.0, 0.0));
+    w.opt_decimal("wind_direction", scaled(u(80, 9), 360, 1.0, 0.0));
+    w.opt_decimal("wind_gust_direction", scaled(u(89, 9), 360, 1.0, 0.0));
+    w.opt_decimal("air_temperature", (air_temperature != -1024).then(|| air_temperature as f64 / 10.0));
+    w.opt_decimal("relative_humidity", scaled(u(109, 7), 101, 1.0, 0.0));
+    w.opt_decimal("dew_point", (dew_point != 501).then(|| dew_point as f64 / 10.0));
+    w.opt_decimal("air_pressure", scaled(u(126, 9), 403, 1.0, 799.0));
+    w.opt_uint("air_pressure_tendency", code(135, 2, 3));
+    w.flag("visibility_greater_than", u(137, 1));
+    w.opt_decimal("visibility", scaled(u(138, 7), 127, 10.0, 0.0));
+    w.opt_decimal("water_level", scaled(u(145, 12), 4001, 100.0, -1000.0));
+    w.opt_uint("water_level_trend", code(157, 2, 3));
+    w.opt_decimal("surface_current_speed", scaled(u(159, 8), 251, 10.0, 0.0));
+    w.opt_decimal("surface_current_direction", scaled(u(167, 9), 360, 1.0, 0.0));
+    w.opt_decimal("current_2_speed", scaled(u(176, 8), 251, 10.0, 0.0));
+    w.opt_decimal("current_2_direction", scaled(u(184, 9), 360, 1.0, 0.0));
+    w.opt_decimal("current_2_depth", scaled(u(193, 5), 31, 1.0, 0.0));
+    w.opt_decimal("current_3_speed", scaled(u(198, 8), 251, 10.0, 0.0));
+    w.opt_decimal("current_3_direction", scaled(u(206, 9), 360, 1.0, 0.0));
+    w.opt_decimal("current_3_depth", scaled(u(215, 5), 31, 1.0, 0.0));
+    w.opt_decimal("wave_height", scaled(u(220, 8), 251, 10.0, 0.0));
+    w.opt_decimal("wave_period", scaled(u(228, 6), 61, 1.0, 0.0));
+    w.opt_decimal("wave_direction", scaled(u(234, 9), 360, 1.0, 0.0));
+    w.opt_decimal("swell_height", scaled(u(243, 8), 251, 10.0, 0.0));
+    w.opt_decimal("swell_period", scaled(u(251, 6), 61, 1.0, 0.0));
+    w.opt_decimal("swell_direction", scaled(u(257, 9), 360, 1.0, 0.0));
+    w.opt_uint("sea_state", code(266, 4, 13));
+    w.opt_decimal("water_temperature", (water_temperature != 501).then(|| water_temperature as f64 / 10.0));
+    w.opt_uint("precipitation_type", code(280, 3, 7));
+    w.opt_decimal("salinity", scaled(u(283, 9), 501, 10.0, 0.0));
+    w.opt_uint("ice", code(292, 2, 3));
+}
+
+// ─── Per-message-type fields (shared by single-line and multiline output) ────
+
+/// Decode the payload bits and append every field from `"mmsi"` onwards.
+/// `bits` is the validated payload length, without fill bits.
+/// `received_at` is the reception time in epoch seconds, used to resolve the type 5 ETA.
+fn append_message_fields(pv: &[u8], bits: usize, message_type: u64, received_at: &str, w: &mut JsonFields) {
     match message_type {
-        1 | 2 | 3 => {
+        1..=3 => {
             let mmsi    = pick_u64(pv, 8, 30);
-            let lat     = pick_i64(pv, 89, 27) as f64 / 600_000.0;
-            let lon     = pick_i64(pv, 61, 28) as f64 / 600_000.0;
+            let lat     = pick_i64(pv, 89, 27);
+            let lon     = pick_i64(pv, 61, 28);
             let pos_acc = pick_u64(pv, 60, 1);
             let sog     = pick_u64(pv, 50, 10);
-            let cog     = pick_u64(pv, 116, 27);
+            let cog     = pick_u64(pv, 116, 12);
             let nav     = pick_u64(pv, 38, 4);
-
-            push_u64_str(out, mmsi);
-            out.push_str(",\"latitude\":");
-            push_f64(out, lat);
-            out.push_str(",\"longitude\":");
-            push_f64(out, lon);
-            out.push_str(",\"call_sign\":\"\",\"destination\":\"\",\"name\":\"\",\"ship_type\":\"\",\"eta\":\"\",\"draught\":\"\",\"imo\":\"\",\"course_over_ground\":");
-            push_u64_str(out, cog);
-            out.push_str(",\"position_accuracy\":");
-            push_u64_str(out, pos_acc);
-            out.push_str(",\"speed_over_ground\":");
-            push_u64_str(out, sog);
-            out.push_str(",\"navigation_status\":");
-            push_u64_str(out, nav);
+            let rot     = pick_i64(pv, 42, 8);
+            let heading = pick_u64(pv, 128, 9);
+            let second  = pick_u64(pv, 137, 6);
+            let maneuver = pick_u64(pv, 143, 2);
+            let raim    = pick_u64(pv, 148, 1);
+
+            w.uint("mmsi", mmsi);
+            w.coordinate("latitude", lat, 600_000.0, 90);
+            w.coordinate("longitude", lon, 600_000.0, 180);
+            w.blank(STATIC_COLUMNS);
+            w.kinematic("course_over_ground", cog, 3600, 10.0);
+            w.flag("position_accuracy", pos_acc);
+            w.kinematic("speed_over_ground", sog, 1023, 10.0);
+            w.uint("navigation_status", nav);
+            // With physical units rate_of_turn already is in °/min
+            if w.format.physical_units {
+                w.opt_float("rate_of_turn", rot_degrees_per_minute(rot));
+            } else {
+                w.int("rate_of_turn", rot);
+                w.opt_decimal("rate_of_turn_degrees", rot_degrees_per_minute(rot));
+            }
+            w.kinematic("heading", heading, 360, 1.0);
+            w.uint("timestamp", second);
+            w.uint("maneuver", maneuver);
+            w.flag("raim", raim);
+            // Types 1 and 2 are SOTDMA, type 3 is ITDMA
+            append_comm_state_fields(pv, 149, message_type == 3, w);
+        }
+        4 | 11 => {
+            // Base station report (4) and UTC/date response (11) share one layout
+            let mmsi    = pick_u64(pv, 8, 30);
+            let year    = pick_u64(pv, 38, 14);
+            let month   = pick_u64(pv, 52, 4);
+            let day     = pick_u64(pv, 56, 5);
+            let hour    = pick_u64(pv, 61, 5);
+            let minute  = pick_u64(pv, 66, 6);
+            let second  = pick_u64(pv, 72, 6);
+            let pos_acc = pick_u64(pv, 78, 1);
+            let lon     = pick_i64(pv, 79, 28);
+            let lat     = pick_i64(pv, 107, 27);
+            let epfd    = pick_u64(pv, 134, 4);
+            let raim    = pick_u64(pv, 148, 1);
+
+            w.uint("mmsi", mmsi);
+            w.coordinate("latitude", lat, 600_000.0, 90);
+            w.coordinate("longitude", lon, 600_000.0, 180);
+            w.blank(STATIC_COLUMNS);
+            w.blank(&["course_over_ground"]);
+            w.flag("position_accuracy", pos_acc);
+            w.blank(&["speed_over_ground", "navigation_status"]);
+            w.uint("utc_year", year);
+            w.uint("utc_month", month);
+            w.uint("utc_day", day);
+            w.uint("utc_hour", hour);
+            w.uint("utc_minute", minute);
+            w.uint("utc_second", second);
+            w.uint("epfd", epfd);
+            w.flag("raim", raim);
         }
         5 => {
             let mmsi        = pick_u64(pv, 8, 30);
-            let call_sign   = pick_string(pv, 70, 42);
-            let name        = pick_string(pv, 112, 120);
-            let ship_type   = pick_u64(pv, 232, 8);
+            let ais_version = pick_u64(pv, 38, 2);
             let imo         = pick_u64(pv, 40, 30);
-            let destination = pick_string(pv, 302, 120);
+            let call_sign   = pick_string(pv, 70, 7);
+            let name        = pick_string(pv, 112, 20);
+            let ship_type   = pick_u64(pv, 232, 8);
+            let to_bow      = pick_u64(pv, 240, 9);
+            let to_stern    = pick_u64(pv, 249, 9);
+            let to_port     = pick_u64(pv, 258, 6);
+            let to_stbd     = pick_u64(pv, 264, 6);
+            let epfd        = pick_u64(pv, 270, 4);
+            let destination = pick_string(pv, 302, 20);
+            let dte         = pick_u64(pv, 422, 1);
             let month       = pick_u64(pv, 274, 4);
             let day         = pick_u64(pv, 278, 5);
-            let hour        = pick_u64(pv, 278, 5);
+            let hour        = pick_u64(pv, 283, 5);
             let minute      = pick_u64(pv, 288, 6);
-            let datestub    = minute * 60 + hour * 3600 + day * 86400 + month * 2678400;
-            let year: f64   = if !sat_time.is_empty() {
-                sat_time.parse::<f64>().unwrap_or(0.0) / 31_536_000.0
-            } else { 0.0 };
-            let eta     = ((year * 31_536_000.0) as u64) + datestub;
+            let eta = received_at.parse::<i64>().ok()
+                .and_then(|received| resolve_eta(received, month, day, hour, minute));
+            // Draught in 1/10 m, 0 = not available
             let draught = pick_u64(pv, 294, 8);
-
-            push_u64_str(out, mmsi);
-            out.push_str(",\"latitude\":0.0,\"longitude\":0.0,\"call_sign\":");
-            push_escaped_str(out, &call_sign);
-            out.push_str(",\"destination\":");
-            push_escaped_str(out, &destination);
-            out.push_str(",\"name\":");
-            push_escaped_str(out, &name);
-            out.push_str(",\"ship_type\":");
-            push_u64_str(out, ship_type);
-            out.push_str(",\"eta\":");
-            push_u64_str(out, eta);
-            out.push_str(",\"draught\":");
-            push_u64_str(out, draught);
-            out.push_str(",\"imo\":");
-            push_u64_str(out, imo);
-            out.push_str(",\"course_over_ground\":\"\",\"position_accuracy\":\"\",\"speed_over_ground\":\"\",\"navigation_status\":\"\"");
+            // Dimensions of 0 are "not available", so a zero sum has no length/beam
+            let length = to_bow + to_stern;
+            let beam   = to_port + to_stbd;
+
+            w.uint("mmsi", mmsi);
+            w.blank_position();
+            w.text("call_sign", &call_sign);
+            w.text("destination", &destination);
+            w.text("name", &name);
+            w.uint("ship_type", ship_type);
+            w.timestamp("eta", eta);
+            w.opt_decimal("draught", (draught != 0).then(|| draught as f64 / 10.0));
+            w.uint("imo", imo);
+            w.blank(KINEMATIC_COLUMNS);
+            w.uint("ais_version", ais_version);
+            w.uint("to_bow", to_bow);
+            w.uint("to_stern", to_stern);
+            w.uint("to_port", to_port);
+            w.uint("to_starboard", to_stbd);
+            w.opt_uint("length", (length != 0).then_some(length));
+            w.opt_uint("beam", (beam != 0).then_some(beam));
+            w.uint("epfd", epfd);
+            w.flag("dte", dte);
+            w.opt_uint("eta_month", (month != 0).then_some(month));
+            w.opt_uint("eta_day", (day != 0).then_some(day));
+            w.opt_uint("eta_hour", (hour != 24).then_some(hour));
+            w.opt_uint("eta_minute", (minute != 60).then_some(minute));
         }
-        18 => {
-            let mmsi    = pick_u64(pv, 8, 30);
-            let lon     = pick_i64(pv, 57, 28) as f64 / 600_000.0;
-            let lat     = pick_i64(pv, 85, 27) as f64 / 600_000.0;
-            let pos_acc = pick_u64(pv, 56, 1);
-            let cog     = pick_u64(pv, 112, 12);
-            let sog     = pick_u64(pv, 46, 10);
-
-            push_u64_str(out, mmsi);
-            out.push_str(",\"latitude\":");
-            push_f64(out, lat);
-            out.push_str(",\"longitude\":");
-            push_f64(out, lon);
-            out.push_str(",\"call_sign\":\"\",\"destination\":\"\",\"name\":\"\",\"ship_type\":\"\",\"eta\":\"\",\"draught\":\"\",\"imo\":\"\",\"course_over_ground\":");
-            push_u64_str(out, cog);
-            out.push_str(",\"position_accuracy\":");
-            push_u64_str(out, pos_acc);
-            out.push_str(",\"speed_over_ground\":");
-            push_u64_str(out, sog);
-            out.push_str(",\"navigation_status\":\"\"");
+        6 | 8 => {
+            // Binary addressed (6) / broadcast (8): envelope plus raw application data,
+            // decoded further when an application decoder is registered for (DAC, FI)
+            let mmsi = pick_u64(pv, 8, 30);
+            let (dac_start, applications) = if message_type == 6 {
+                (72, ADDRESSED_APPLICATIONS)
+            } else {
+                (40, BROADCAST_APPLICATIONS)
+            };
+            let dac = pick_u64(pv, dac_start, 10);
+            let fi  = pick_u64(pv, dac_start + 10, 6);
+            let data_start = dac_start + 16;
+            let data_bits  = bits.saturating_sub(data_start);
+
+            w.uint("mmsi", mmsi);
+            w.blank_position();
+            w.blank(STATIC_COLUMNS);
+            w.blank(KINEMATIC_COLUMNS);
+            if message_type == 6 {
+                w.uint("sequence_number", pick_u64(pv, 38, 2));
+                w.uint("dest_mmsi", pick_u64(pv, 40, 30));
+                w.flag("retransmit", pick_u64(pv, 70, 1));
+            }
+            w.uint("dac", dac);
+            w.uint("fi", fi);
+            w.hex("application_data", pv, data_start, data_bits);
+            if let Some(decode) = find_application(applications, dac, fi, data_bits) {
+                decode(pv, data_start, w);
+            }
         }
-        19 => {
+        7 | 13 => {
+            // Binary (7) / safety-related (13) acknowledgement of up to four
+            // messages, each identified by destination MMSI and sequence number
+            const ACKS: [(&str, &str); 4] = [
+                ("ack_mmsi_1", "ack_sequence_1"),
+                ("ack_mmsi_2", "ack_sequence_2"),
+                ("ack_mmsi_3", "ack_sequence_3"),
+                ("ack_mmsi_4", "ack_sequence_4"),
+            ];
+            let mmsi = pick_u64(pv, 8, 30);
+            let acks = bits.saturating_sub(40) / 32;
+
+            w.uint("mmsi", mmsi);
+            w.blank_position();
+            w.blank(STATIC_COLUMNS);
+            w.blank(KINEMATIC_COLUMNS);
+            for (i, (mmsi_name, seq_name)) in ACKS.iter().take(acks).enumerate() {
+                w.uint(mmsi_name, pick_u64(pv, 40 + i * 32, 30));
+                w.uint(seq_name, pick_u64(pv, 70 + i * 32, 2));
+            }
+        }
+        9 => {
+            // SAR aircraft: SOG is in whole knots, not 1/10 knot as for vessels
+            let mmsi     = pick_u64(pv, 8, 30);
+            let altitude = pick_u64(pv, 38, 12);
+            let sog      = pick_u64(pv, 50, 10);
+            let pos_acc  = pick_u64(pv, 60, 1);
+            let lon      = pick_i64(pv, 61, 28);
+            let lat      = pick_i64(pv, 89, 27);
+            let cog      = pick_u64(pv, 116, 12);
+            let second   = pick_u64(pv, 128, 6);
+            let dte      = pick_u64(pv, 142, 1);
+            let assigned = pick_u64(pv, 146, 1);
+            let raim     = pick_u64(pv, 147, 1);
+
+            w.uint("mmsi", mmsi);
+            w.coordinate("latitude", lat, 600_000.0, 90);
+            w.coordinate("longitude", lon, 600_000.0, 180);
+            w.blank(STATIC_COLUMNS);
+            w.kinematic("course_over_ground", cog, 3600, 10.0);
+            w.flag("position_accuracy", pos_acc);
+            w.kinematic("speed_over_ground", sog, 1023, 1.0);
+            w.blank(&["navigation_status"]);
+            w.kinematic("altitude", altitude, 4095, 1.0);
+            w.uint("timestamp", second);
+            w.flag("dte", dte);
+            w.flag("assigned", assigned);
+            w.flag("raim", raim);
+        }
+        10 => {
+            // UTC/date inquiry, answered by a type 11
+            let mmsi = pick_u64(pv, 8, 30);
+
+            w.uint("mmsi", mmsi);
+            w.blank_position();
+            w.blank(STATIC_COLUMNS);
+            w.blank(KINEMATIC_COLUMNS);
+            w.uint("dest_mmsi", pick_u64(pv, 40, 30));
+        }
+        12 | 14 => {
+            // Safety-related text: addressed (12) or broadcast (14)
+            let mmsi = pick_u64(pv, 8, 30);
+            let text_start = if message_type == 12 { 72 } else { 40 };
+            let text_chars = bits.saturating_sub(text_start) / AIS_CHAR_BITS;
+            let text = pick_string(pv, text_start, text_chars);
+
+            w.uint("mmsi", mmsi);
+            w.blank_position();
+            w.blank(STATIC_COLUMNS);
+            w.blank(KINEMATIC_COLUMNS);
+            if message_type == 12 {
+                w.uint("sequence_number", pick_u64(pv, 38, 2));
+                w.uint("dest_mmsi", pick_u64(pv, 40, 30));
+                w.flag("retransmit", pick_u64(pv, 70, 1));
+            }
+            w.text("text", &text);
+        }
+        15 => {
+            // Interrogation: one station for one or two messages, or two stations.
+            // Shorter variants leave the later requests out of the payload.
+            let mmsi = pick_u64(pv, 8, 30);
+
+            w.uint("mmsi", mmsi);
+            w.blank_position();
+            w.blank(STATIC_COLUMNS);
+            w.blank(KINEMATIC_COLUMNS);
+            w.uint("interrogated_mmsi_1", pick_u64(pv, 40, 30));
+            w.uint("message_type_1_1", pick_u64(pv, 70, 6));
+            w.uint("slot_offset_1_1", pick_u64(pv, 76, 12));
+            if bits >= 108 {
+                w.uint("message_type_1_2", pick_u64(pv, 90, 6));
+                w.uint("slot_offset_1_2", pick_u64(pv, 96, 12));
+            }
+            if bits >= 158 {
+                w.uint("interrogated_mmsi_2", pick_u64(pv, 110, 30));
+                w.uint("message_type_2_1", pick_u64(pv, 140, 6));
+                w.uint("slot_offset_2_1", pick_u64(pv, 146, 12));
+            }
+        }
+        16 => {
+            // Assignment mode command for one or two stations
+            let mmsi = pick_u64(pv, 8, 30);
+
+            w.uint("mmsi", mmsi);
+            w.blank_position();
+            w.blank(STATIC_COLUMNS);
+            w.blank(KINEMATIC_COLUMNS);
+            w.uint("dest_mmsi_a", pick_u64(pv, 40, 30));
+            w.uint("offset_a", pick_u64(pv, 70, 12));
+            w.uint("increment_a", pick_u64(pv, 82, 10));
+            if bits >= 144 {
+                w.uint("dest_mmsi_b", pick_u64(pv, 92, 30));
+                w.uint("offset_b", pick_u64(pv, 122, 12));
+                w.uint("increment_b", pick_u64(pv, 134, 10));
+            }
+        }
+        17 => {
+            // DGNSS broadcast: reference station position in 1/10 minute,
+            // followed by an RTCM SC-104 message
+            let mmsi = pick_u64(pv, 8, 30);
+            let lon  = pick_i64(pv, 40, 18);
+            let lat  = pick_i64(pv, 58, 17);
+            let data_bits = bits.saturating_sub(80);
+
+            w.uint("mmsi", mmsi);
+            w.coordinate("latitude", lat, 600.0, 90);
+            w.coordinate("longitude", lon, 600.0, 180);
+            w.blank(STATIC_COLUMNS);
+            w.blank(KINEMATIC_COLUMNS);
+            if data_bits >= 40 {
+                // RTCM SC-104 header: the first 40 bits of the correction data
+                w.uint("rtcm_message_type", pick_u64(pv, 80, 6));
+                w.uint("reference_station_id", pick_u64(pv, 86, 10));
+                w.uint("z_count", pick_u64(pv, 96, 13));
+                w.uint("rtcm_sequence", pick_u64(pv, 109, 3));
+                w.uint("data_words", pick_u64(pv, 112, 5));
+                w.uint("station_health", pick_u64(pv, 117, 3));
+            }
+            w.hex("dgnss_data", pv, 80, data_bits);
+        }
+        18 => {
             let mmsi     = pick_u64(pv, 8, 30);
             let sog      = pick_u64(pv, 46, 10);
-            let cog      = pick_u64(pv, 112, 12);
             let pos_acc  = pick_u64(pv, 56, 1);
-            let name     = pick_string(pv, 143, 120);
+            let lon      = pick_i64(pv, 57, 28);
+            let lat      = pick_i64(pv, 85, 27);
+            let cog      = pick_u64(pv, 112, 12);
+            let heading  = pick_u64(pv, 124, 9);
+            let second   = pick_u64(pv, 133, 6);
+            let cs_unit  = pick_u64(pv, 141, 1);
+            let display  = pick_u64(pv, 142, 1);
+            let dsc      = pick_u64(pv, 143, 1);
+            let band     = pick_u64(pv, 144, 1);
+            let msg22    = pick_u64(pv, 145, 1);
+            let assigned = pick_u64(pv, 146, 1);
+            let raim     = pick_u64(pv, 147, 1);
+            let itdma    = pick_u64(pv, 148, 1);
+
+            w.uint("mmsi", mmsi);
+            w.coordinate("latitude", lat, 600_000.0, 90);
+            w.coordinate("longitude", lon, 600_000.0, 180);
+            w.blank(STATIC_COLUMNS);
+            w.kinematic("course_over_ground", cog, 3600, 10.0);
+            w.flag("position_accuracy", pos_acc);
+            w.kinematic("speed_over_ground", sog, 1023, 10.0);
+            w.blank(&["navigation_status"]);
+            w.kinematic("heading", heading, 360, 1.0);
+            w.uint("timestamp", second);
+            // 1 = carrier-sense (CS) unit, 0 = SOTDMA unit
+            w.flag("cs_unit", cs_unit);
+            w.flag("display", display);
+            w.flag("dsc", dsc);
+            w.flag("band", band);
+            w.flag("msg22", msg22);
+            w.flag("assigned", assigned);
+            w.flag("raim", raim);
+            // The communication state selector picks SOTDMA (0) or ITDMA (1);
+            // CS units always send the fixed ITDMA value 1100000000000000110
+            w.flag("comm_state_itdma", itdma);
+            append_comm_state_fields(pv, 149, itdma == 1, w);
+        }
+        19 => {
+            let mmsi      = pick_u64(pv, 8, 30);
+            let sog       = pick_u64(pv, 46, 10);
+            let pos_acc   = pick_u64(pv, 56, 1);
+            let lon       = pick_i64(pv, 57, 28);
+            let lat       = pick_i64(pv, 85, 27);
+            let cog       = pick_u64(pv, 112, 12);
+            let heading   = pick_u64(pv, 124, 9);
+            let second    = pick_u64(pv, 133, 6);
+            let name      = pick_string(pv, 143, 20);
             let ship_type = pick_u64(pv, 263, 8);
+            let to_bow    = pick_u64(pv, 271, 9);
+            let to_stern  = pick_u64(pv, 280, 9);
+            let to_port   = pick_u64(pv, 289, 6);
+            let to_stbd   = pick_u64(pv, 295, 6);
+            let epfd      = pick_u64(pv, 301, 4);
+            let raim      = pick_u64(pv, 305, 1);
+            let dte       = pick_u64(pv, 306, 1);
+            let assigned  = pick_u64(pv, 307, 1);
+
+            w.uint("mmsi", mmsi);
+            w.coordinate("latitude", lat, 600_000.0, 90);
+            w.coordinate("longitude", lon, 600_000.0, 180);
+            w.blank(&["call_sign", "destination"]);
+            w.text("name", &name);
+            w.uint("ship_type", ship_type);
+            w.blank(&["eta", "draught", "imo"]);
+            w.kinematic("course_over_ground", cog, 3600, 10.0);
+            w.flag("position_accuracy", pos_acc);
+            w.kinematic("speed_over_ground", sog, 1023, 10.0);
+            w.blank(&["navigation_status"]);
+            w.kinematic("heading", heading, 360, 1.0);
+            w.uint("timestamp", second);
+            w.uint("to_bow", to_bow);
+            w.uint("to_stern", to_stern);
+            w.uint("to_port", to_port);
+            w.uint("to_starboard", to_stbd);
+            w.uint("epfd", epfd);
+            w.flag("raim", raim);
+            w.flag("dte", dte);
+            w.flag("assigned", assigned);
+        }
+        20 => {
+            // Data link management: up to four reserved slot blocks
+            const RESERVATIONS: [[&str; 4]; 4] = [
+                ["slot_offset_1", "slots_1", "timeout_1", "increment_1"],
+                ["slot_offset_2", "slots_2", "timeout_2", "increment_2"],
+                ["slot_offset_3", "slots_3", "timeout_3", "increment_3"],
+                ["slot_offset_4", "slots_4", "timeout_4", "increment_4"],
+            ];
+            let mmsi = pick_u64(pv, 8, 30);
+            let reservations = bits.saturating_sub(40) / 30;
+
+            w.uint("mmsi", mmsi);
+            w.blank_position();
+            w.blank(STATIC_COLUMNS);
+            w.blank(KINEMATIC_COLUMNS);
+            for (i, [offset, slots, timeout, increment]) in RESERVATIONS.iter().take(reservations).enumerate() {
+                let start = 40 + i * 30;
+                w.uint(offset, pick_u64(pv, start, 12));
+                w.uint(slots, pick_u64(pv, start + 12, 4));
+                w.uint(timeout, pick_u64(pv, start + 16, 3));
+                w.uint(increment, pick_u64(pv, start + 19, 11));
+            }
+        }
+        21 => {
+            let mmsi      = pick_u64(pv, 8, 30);
+            let aid_type  = pick_u64(pv, 38, 5);
+            let mut name  = pick_string(pv, 43, 20);
+            let pos_acc   = pick_u64(pv, 163, 1);
+            let lon       = pick_i64(pv, 164, 28);
+            let lat       = pick_i64(pv, 192, 27);
+            let to_bow    = pick_u64(pv, 219, 9);
+            let to_stern  = pick_u64(pv, 228, 9);
+            let to_port   = pick_u64(pv, 237, 6);
+            let to_stbd   = pick_u64(pv, 243, 6);
+            let epfd      = pick_u64(pv, 249, 4);
+            let second    = pick_u64(pv, 253, 6);
+            let off_pos   = pick_u64(pv, 259, 1);
+            let raim      = pick_u64(pv, 268, 1);
+            let virtual_aid = pick_u64(pv, 269, 1);
+            let assigned  = pick_u64(pv, 270, 1);
+            // Names longer than 20 characters continue in a name extension of
+            // up to 14 characters, filling whatever bits follow the fixed part
+            let ext_chars = bits.saturating_sub(272) / AIS_CHAR_BITS;
+            if ext_chars > 0 {
+                name.push_str(&pick_string(pv, 272, ext_chars.min(14)));
+            }
 
-            push_u64_str(out, mmsi);
-            out.push_str(",\"latitude\":0.0,\"longitude\":0.0,\"call_sign\":\"\",\"destination\":\"\",\"name\":");
-            push_escaped_str(out, &name);
-            out.push_str(",\"ship_type\":");
-            push_u64_str(out, ship_type);
-            out.push_str(",\"eta\":\"\",\"draught\":\"\",\"imo\":\"\",\"course_over_ground\":");
-            push_u64_str(out, cog);
-            out.push_str(",\"position_accuracy\":");
-            push_u64_str(out, pos_acc);
-            out.push_str(",\"speed_over_ground\":");
-            push_u64_str(out, sog);
-            out.push_str(",\"navigation_status\":\"\"");
+            w.uint("mmsi", mmsi);
+            w.coordinate("latitude", lat, 600_000.0, 90);
+            w.coordinate("longitude", lon, 600_000.0, 180);
+            w.blank(&["call_sign", "destination"]);
+            w.text("name", &name);
+            w.blank(&["ship_type", "eta", "draught", "imo", "course_over_ground"]);
+            w.flag("position_accuracy", pos_acc);
+            w.blank(&["speed_over_ground", "navigation_status"]);
+            w.uint("aid_type", aid_type);
+            w.uint("to_bow", to_bow);
+            w.uint("to_stern", to_stern);
+            w.uint("to_port", to_port);
+            w.uint("to_starboard", to_stbd);
+            w.uint("epfd", epfd);
+            w.uint("timestamp", second);
+            w.flag("off_position", off_pos);
+            w.flag("raim", raim);
+            w.flag("virtual_aid", virtual_aid);
+            w.flag("assigned", assigned);
+        }
+        22 => {
+            // Channel management, addressed to two stations or broadcast for
+            // a rectangular area given by its NE and SW corners in 1/10 minute
+            let mmsi      = pick_u64(pv, 8, 30);
+            let addressed = pick_u64(pv, 139, 1);
+
+            w.uint("mmsi", mmsi);
+            w.blank_position();
+            w.blank(STATIC_COLUMNS);
+            w.blank(KINEMATIC_COLUMNS);
+            w.uint("channel_a", pick_u64(pv, 40, 12));
+            w.uint("channel_b", pick_u64(pv, 52, 12));
+            w.uint("tx_rx_mode", pick_u64(pv, 64, 4));
+            w.flag("low_power", pick_u64(pv, 68, 1));
+            w.flag("addressed", addressed);
+            if addressed == 1 {
+                w.uint("dest_mmsi_1", pick_u64(pv, 69, 30));
+                w.uint("dest_mmsi_2", pick_u64(pv, 104, 30));
+            } else {
+                w.coordinate("ne_latitude", pick_i64(pv, 87, 17), 600.0, 90);
+                w.coordinate("ne_longitude", pick_i64(pv, 69, 18), 600.0, 180);
+                w.coordinate("sw_latitude", pick_i64(pv, 122, 17), 600.0, 90);
+                w.coordinate("sw_longitude", pick_i64(pv, 104, 18), 600.0, 180);
+            }
+            w.flag("band_a", pick_u64(pv, 140, 1));
+            w.flag("band_b", pick_u64(pv, 141, 1));
+            w.uint("zone_size", pick_u64(pv, 142, 3));
+        }
+        23 => {
+            // Group assignment command for the stations in an area (1/10 minute
+            // corners), optionally restricted by station type and ship type
+            let mmsi = pick_u64(pv, 8, 30);
+
+            w.uint("mmsi", mmsi);
+            w.blank_position();
+            w.blank(STATIC_COLUMNS);
+            w.blank(KINEMATIC_COLUMNS);
+            w.coordinate("ne_latitude", pick_i64(pv, 58, 17), 600.0, 90);
+            w.coordinate("ne_longitude", pick_i64(pv, 40, 18), 600.0, 180);
+            w.coordinate("sw_latitude", pick_i64(pv, 93, 17), 600.0, 90);
+            w.coordinate("sw_longitude", pick_i64(pv, 75, 18), 600.0, 180);
+            w.uint("station_type", pick_u64(pv, 110, 4));
+            w.uint("target_ship_type", pick_u64(pv, 114, 8));
+            w.uint("tx_rx_mode", pick_u64(pv, 144, 2));
+            w.uint("report_interval", pick_u64(pv, 146, 4));
+            w.uint("quiet_time", pick_u64(pv, 150, 4));
+        }
+        24 => {
+            // Class B static data arrives in two halves: part A carries the
+            // name, part B the ship type, vendor, call sign and dimensions
+            let mmsi = pick_u64(pv, 8, 30);
+            let part = pick_u64(pv, 38, 2);
+
+            w.uint("mmsi", mmsi);
+            w.blank_position();
+            match part {
+                0 => {
+                    let name = pick_string(pv, 40, 20);
+                    w.blank(&["call_sign", "destination"]);
+                    w.text("name", &name);
+                    w.blank(&["ship_type"]);
+                }
+                1 => {
+                    let call_sign = pick_string(pv, 90, 7);
+                    let ship_type = pick_u64(pv, 40, 8);
+                    w.text("call_sign", &call_sign);
+                    w.blank(&["destination", "name"]);
+                    w.uint("ship_type", ship_type);
+                }
+                // Parts 2 and 3 are reserved: nothing beyond the MMSI is defined
+                _ => w.blank(&["call_sign", "destination", "name", "ship_type"]),
+            }
+            w.blank(&["eta", "draught", "imo"]);
+            w.blank(KINEMATIC_COLUMNS);
+            w.uint("part_number", part);
+            if part == 1 {
+                append_class_b_part_b_fields(pv, mmsi, w);
+            }
+        }
+        25 | 26 => {
+            // Single-slot (25) / multi-slot (26) binary message: optional destination,
+            // optional (DAC, FI) application identifier, then the data. Type 26
+            // ends with a selector bit and a 19-bit communication state.
+            let mmsi       = pick_u64(pv, 8, 30);
+            let addressed  = pick_u64(pv, 38, 1);
+            let structured = pick_u64(pv, 39, 1);
+            let data_end = if message_type == 26 { bits.saturating_sub(20) } else { bits };
+            let mut data_start = if addressed == 1 { 70 } else { 40 };
+
+            w.uint("mmsi", mmsi);
+            w.blank_position();
+            w.blank(STATIC_COLUMNS);
+            w.blank(KINEMATIC_COLUMNS);
+            w.flag("addressed", addressed);
+            w.flag("structured", structured);
+            if addressed == 1 {
+                w.uint("dest_mmsi", pick_u64(pv, 40, 30));
+            }
+            let application = if structured == 1 {
+                let dac = pick_u64(pv, data_start, 10);
+                let fi  = pick_u64(pv, data_start + 10, 6);
+                w.uint("dac", dac);
+                w.uint("fi", fi);
+                data_start += 16;
+                let applications = if addressed == 1 { ADDRESSED_APPLICATIONS } else { BROADCAST_APPLICATIONS };
+                find_application(applications, dac, fi, data_end.saturating_sub(data_start))
+            } else {
+                None
+            };
+            w.hex("application_data", pv, data_start, data_end.saturating_sub(data_start));
+            if let Some(decode) = application {
+                decode(pv, data_start, w);
+            }
+            if message_type == 26 && bits >= data_start + 20 {
+                let itdma = pick_u64(pv, data_end, 1);
+                w.flag("comm_state_itdma", itdma);
+                append_comm_state_fields(pv, data_end + 1, itdma == 1, w);
+            }
+        }
+        27 => {
+            // Long-range broadcast for satellite reception: position in 1/10 minute,
+            // SOG in whole knots and COG in whole degrees
+            let mmsi    = pick_u64(pv, 8, 30);
+            let pos_acc = pick_u64(pv, 38, 1);
+            let raim    = pick_u64(pv, 39, 1);
+            let nav     = pick_u64(pv, 40, 4);
+            let lon     = pick_i64(pv, 44, 18);
+            let lat     = pick_i64(pv, 62, 17);
+            let sog     = pick_u64(pv, 79, 6);
+            let cog     = pick_u64(pv, 85, 9);
+            let latency = pick_u64(pv, 94, 1);
+
+            w.uint("mmsi", mmsi);
+            w.coordinate("latitude", lat, 600.0, 90);
+            w.coordinate("longitude", lon, 600.0, 180);
+            w.blank(STATIC_COLUMNS);
+            w.kinematic("course_over_ground", cog, 360, 1.0);
+            w.flag("position_accuracy", pos_acc);
+            w.kinematic("speed_over_ground", sog, 63, 1.0);
+            w.uint("navigation_status", nav);
+            w.text("position_resolution", "low");
+            w.flag("raim", raim);
+            w.flag("position_latency", latency);
         }
         _ => {
-            // Unknown type — all remaining fields are defaults
-            out.push_str("\"\"");
-            out.push_str(",\"latitude\":0.0,\"longitude\":0.0");
-            out.push_str(",\"call_sign\":\"\",\"destination\":\"\",\"name\":\"\",\"ship_type\":\"\",\"eta\":\"\",\"draught\":\"\",\"imo\":\"\"");
-            out.push_str(",\"course_over_ground\":\"\",\"position_accuracy\":\"\",\"speed_over_ground\":\"\",\"navigation_status\":\"\"");
+            // Not reached: validate_payload rejects unknown types
+            w.blank(&["mmsi"]);
+            w.blank_position();
+            w.blank(STATIC_COLUMNS);
+            w.blank(KINEMATIC_COLUMNS);
+        }
+    }
+}
+
+/// Convert a raw ROT_AIS value to degrees per minute: ROT = (ROT_AIS / 4.733)²,
+/// keeping the sign. None for "not available" (-128) and for ±127, which only
+/// says the vessel turns faster than 5°/30 s without a rate of turn indicator.
+fn rot_degrees_per_minute(rot: i64) -> Option<f64> {
+    match rot {
+        -128 | -127 | 127 => None,
+        r => {
+            let magnitude = (r.abs() as f64 / 4.733).powi(2);
+            Some(if r < 0 { -magnitude } else { magnitude })
         }
     }
-    out.push('}');
+}
+
+/// The 19-bit SOTDMA or ITDMA communication state starting at bit `index`:
+/// the raw value plus its sync state and slot allocation sub-fields
+fn append_comm_state_fields(pv: &[u8], index: usize, itdma: bool, w: &mut JsonFields) {
+    w.uint("radio_status", pick_u64(pv, index, 19));
+    w.uint("sync_state", pick_u64(pv, index, 2));
+    if itdma {
+        w.uint("slot_increment", pick_u64(pv, index + 2, 13));
+        w.uint("number_of_slots", pick_u64(pv, index + 15, 3));
+        w.flag("keep_flag", pick_u64(pv, index + 18, 1));
+    } else {
+        w.uint("slot_timeout", pick_u64(pv, index + 2, 3));
+        w.uint("sub_message", pick_u64(pv, index + 5, 14));
+    }
+}
+
+/// Type 24 part B fields beyond call sign and ship type. Auxiliary craft
+/// (MMSI 98xxxxxxx) report their mothership's MMSI in place of dimensions.
+fn append_class_b_part_b_fields(pv: &[u8], mmsi: u64, w: &mut JsonFields) {
+    let vendor_id = pick_string(pv, 48, 3);
+    let model     = pick_u64(pv, 66, 4);
+    let serial    = pick_u64(pv, 70, 20);
+
+    w.text("vendor_id", &vendor_id);
+    w.uint("model", model);
+    w.uint("serial", serial);
+    if mmsi / 10_000_000 == 98 {
+        w.uint("mothership_mmsi", pick_u64(pv, 132, 30));
+    } else {
+        w.uint("to_bow", pick_u64(pv, 132, 9));
+        w.uint("to_stern", pick_u64(pv, 141, 9));
+        w.uint("to_port", pick_u64(pv, 150, 6));
+        w.uint("to_starboard", pick_u64(pv, 156, 6));
+    }
 }
 
 // ─── JSON serialisation for assembled multiline PositionReport ───────────────
 
-fn append_report_json(line: &PositionReport, out: &mut String) {
-    out.push_str("{\"landfall_time\":");
-    push_safe_str(out, &line.landfall_time);
-    out.push_str(",\"group\":");
-    push_safe_str(out, &line.group);
-    out.push_str(",\"satellite_acquisition_time\":");
-    push_safe_str(out, &line.satellite_acquisition_time);
-    out.push_str(",\"source\":");
-    push_safe_str(out, &line.source);
-    out.push_str(",\"channel\":");
-    push_safe_str(out, &line.channel);
-    out.push_str(",\"raw_payload\":");
-    push_safe_str(out, &line.raw_payload);
-    out.push_str(",\"message_type\":");
-    push_u64(out, line.message_type);
-    out.push_str(",\"message_class\":");
-    push_safe_str(out, &line.message_class);
-    out.push_str(",\"mmsi\":");
-    push_safe_str(out, &line.mmsi);
-    out.push_str(",\"latitude\":");
-    push_f64(out, line.latitude);
-    out.push_str(",\"longitude\":");
-    push_f64(out, line.longitude);
-    out.push_str(",\"call_sign\":");
-    push_escaped_str(out, &line.call_sign);
-    out.push_str(",\"destination\":");
-    push_escaped_str(out, &line.destination);
-    out.push_str(",\"name\":");
-    push_escaped_str(out, &line.name);
-    out.push_str(",\"ship_type\":");
-    push_safe_str(out, &line.ship_type);
-    out.push_str(",\"eta\":");
-    push_safe_str(out, &line.eta);
-    out.push_str(",\"draught\":");
-    push_safe_str(out, &line.draught);
-    out.push_str(",\"imo\":");
-    push_safe_str(out, &line.imo);
-    out.push_str(",\"course_over_ground\":");
-    push_safe_str(out, &line.course_over_ground);
-    out.push_str(",\"position_accuracy\":");
-    push_safe_str(out, &line.position_accuracy);
-    out.push_str(",\"speed_over_ground\":");
-    push_safe_str(out, &line.speed_over_ground);
-    out.push_str(",\"navigation_status\":");
-    push_safe_str(out, &line.navigation_status);
-    out.push('}');
-}
-
-// ─── Decode AIS payload into a PositionReport (for multiline assembled msgs) ─
-
-fn decode_payload(mut line: PositionReport) -> PositionReport {
+/// The payload must have passed `validate_payload`. Returns the decoded message type.
+fn append_report_json(line: &PositionReport, format: OutputFormat, out: &mut String) -> u64 {
     let payload = Payload::from_str(&line.raw_payload);
     let pv = payload.as_slice();
-    line.message_type = pick_u64(pv, 0, 6);
-
-    match line.message_type {
-        1 | 2 | 3 => {
-            line.mmsi              = pick_u64(pv, 8, 30).to_string();
-            line.latitude          = pick_i64(pv, 89, 27) as f64 / 600_000.0;
-            line.longitude         = pick_i64(pv, 61, 28) as f64 / 600_000.0;
-            line.position_accuracy = pick_u64(pv, 60, 1).to_string();
-            line.speed_over_ground = pick_u64(pv, 50, 10).to_string();
-            line.course_over_ground= pick_u64(pv, 116, 27).to_string();
-            line.navigation_status = pick_u64(pv, 38, 4).to_string();
-        }
-        5 => {
-            line.mmsi        = pick_u64(pv, 8, 30).to_string();
-            line.call_sign   = pick_string(pv, 70, 42);
-            line.name        = pick_string(pv, 112, 120);
-            line.ship_type   = pick_u64(pv, 232, 8).to_string();
-            line.imo         = pick_u64(pv, 40, 30).to_string();
-            line.destination = pick_string(pv, 302, 120);
-            let month   = pick_u64(pv, 274, 4);
-            let day     = pick_u64(pv, 278, 5);
-            let hour    = pick_u64(pv, 278, 5);
-            let minute  = pick_u64(pv, 288, 6);
-            let datestub = minute * 60 + hour * 3600 + day * 86400 + month * 2678400;
-            let year: f64 = if !line.satellite_acquisition_time.is_empty() {
-                line.satellite_acquisition_time.parse::<f64>().unwrap_or(0.0) / 31_536_000.0
-            } else { 0.0 };
-            line.eta     = (((year * 31_536_000.0) as u64) + datestub).to_string();
-            line.draught = pick_u64(pv, 294, 8).to_string();
-        }
-        18 => {
-            line.mmsi              = pick_u64(pv, 8, 30).to_string();
-            line.longitude         = pick_i64(pv, 57, 28) as f64 / 600_000.0;
-            line.latitude          = pick_i64(pv, 85, 27) as f64 / 600_000.0;
-            line.position_accuracy = pick_u64(pv, 56, 1).to_string();
-            line.course_over_ground= pick_u64(pv, 112, 12).to_string();
-            line.speed_over_ground = pick_u64(pv, 46, 10).to_string();
-        }
-        19 => {
-            line.mmsi              = pick_u64(pv, 8, 30).to_string();
-            line.speed_over_ground = pick_u64(pv, 46, 10).to_string();
-            line.course_over_ground= pick_u64(pv, 112, 12).to_string();
-            line.position_accuracy = pick_u64(pv, 56, 1).to_string();
-            line.name              = pick_string(pv, 143, 120);
-            line.ship_type         = pick_u64(pv, 263, 8).to_string();
+    let message_type = pick_u64(pv, 0, 6);
+
+    let mut w = begin_record(out, &line.header(), message_type, format);
+    append_message_fields(pv, line.bit_len(), message_type, line.received_at(), &mut w);
+    w.end();
+    message_type
+}
+
+// ─── JSON serialisation for merged Class B static data (type 24 A + B) ──────
+
+/// Combine a type 24 part A and part B of the same MMSI into one record.
+/// Header fields (landfall time, source, channel) are taken from `part_b`.
+fn append_class_b_static_json(
+    part_a: &PositionReport,
+    part_b: &PositionReport,
+    format: OutputFormat,
+    out: &mut String,
+) {
+    let pa = Payload::from_str(&part_a.raw_payload);
+    let pb = Payload::from_str(&part_b.raw_payload);
+    let (av, bv) = (pa.as_slice(), pb.as_slice());
+    let mmsi = pick_u64(bv, 8, 30);
+
+    let header = Header {
+        group: "",
+        raw_payload: "",
+        message_class: "merged",
+        checksum_ok: part_a.checksum_ok && part_b.checksum_ok,
+        ..part_b.header()
+    };
+    let mut w = begin_record(out, &header, 24, format);
+    w.uint("mmsi", mmsi);
+    w.blank_position();
+    w.text("call_sign", &pick_string(bv, 90, 7));
+    w.blank(&["destination"]);
+    w.text("name", &pick_string(av, 40, 20));
+    w.uint("ship_type", pick_u64(bv, 40, 8));
+    w.blank(&["eta", "draught", "imo"]);
+    w.blank(KINEMATIC_COLUMNS);
+    append_class_b_part_b_fields(bv, mmsi, &mut w);
+    w.text("raw_payload_part_a", &part_a.raw_payload);
+    w.text("raw_payload_part_b", &part_b.raw_payload);
+    w.end();
+}
+
+// ─── JSON serialisation for type 5 joined with inland static data ───────────
+
+/// Combine a type 5 static report with the inland ship static and voyage data
+/// (DAC 200, FI 10) of the same MMSI. Header fields are taken from `inland`.
+fn append_inland_static_json(
+    static_report: &PositionReport,
+    inland: &PositionReport,
+    format: OutputFormat,
+    out: &mut String,
+) {
+    let ps = Payload::from_str(&static_report.raw_payload);
+    let pi = Payload::from_str(&inland.raw_payload);
+
+    let header = Header {
+        group: "",
+        raw_payload: "",
+        message_class: "merged",
+        checksum_ok: static_report.checksum_ok && inland.checksum_ok,
+        ..inland.header()
+    };
+    let mut w = begin_record(out, &header, 5, format);
+    append_message_fields(ps.as_slice(), static_report.bit_len(), 5, static_report.received_at(), &mut w);
+    append_inland_static_fields(pi.as_slice(), 56, &mut w);
+    w.text("raw_payload_static", &static_report.raw_payload);
+    w.text("raw_payload_inland", &inland.raw_payload);
+    w.end();
+}
+
+// ─── Static data merge selection ─────────────────────────────────────────────
+
+/// Which decoded messages are forwarded to the static data merge thread
+#[derive(Clone, Copy, Default)]
+struct StaticMerge {
+    /// Pair type 24 part A and part B by MMSI
+    class_b: bool,
+    /// Join type 5 with inland static and voyage data (DAC 200, FI 10) by MMSI
+    inland: bool,
+}
+
+impl StaticMerge {
+    fn enabled(&self) -> bool { self.class_b || self.inland }
+
+    #[inline]
+    fn wants(&self, message_type: u64) -> bool {
+        match message_type {
+            24 => self.class_b,
+            5 | 8 => self.inland,
+            _ => false,
         }
-        _ => {}
     }
-    line
 }
 
 // ─── Per-Rayon-thread extraction state ───────────────────────────────────────
 
 struct ExtractionState {
     out_tx: Sender<String>,
-    ml_tx:  Sender<PositionReport>,
+    ml_tx:  Sender<RunLeftovers>,
+    /// Messages for the static data merge thread (None when merging is off)
+    static_tx: Option<Sender<PositionReport>>,
+    /// Rejected lines for the reject file writer (None without a reject file)
+    reject_tx: Option<Sender<String>>,
+    merge:  StaticMerge,
+    format: OutputFormat,
+    checksum: ChecksumPolicy,
     batch:  String,
     count:  usize,
+    rejects: String,
+    /// Multi-part messages within the current run of the input
+    fragments: FragmentCache,
+    /// What the current run could not assemble, for the assembly thread
+    leftovers: RunLeftovers,
 }
 
 impl Clone for ExtractionState {
@@ -474,15 +1799,58 @@ impl Clone for ExtractionState {
         ExtractionState {
             out_tx: self.out_tx.clone(),
             ml_tx:  self.ml_tx.clone(),
+            static_tx: self.static_tx.clone(),
+            reject_tx: self.reject_tx.clone(),
+            merge:  self.merge,
+            format: self.format,
+            checksum: self.checksum,
             batch:  String::with_capacity(BATCH_SIZE * 350),
             count:  0,
+            rejects: String::new(),
+            fragments: FragmentCache::new(self.fragments.flow_limit as usize),
+            leftovers: RunLeftovers::default(),
         }
     }
 }
 
 impl ExtractionState {
-    fn new(out_tx: Sender<String>, ml_tx: Sender<PositionReport>) -> Self {
-        ExtractionState { out_tx, ml_tx, batch: String::with_capacity(BATCH_SIZE * 350), count: 0 }
+    #[allow(clippy::too_many_arguments)]
+    fn new(
+        out_tx: Sender<String>,
+        ml_tx: Sender<RunLeftovers>,
+        static_tx: Option<Sender<PositionReport>>,
+        reject_tx: Option<Sender<String>>,
+        merge: StaticMerge,
+        format: OutputFormat,
+        checksum: ChecksumPolicy,
+        flow_limit: usize,
+    ) -> Self {
+        ExtractionState {
+            out_tx,
+            ml_tx,
+            static_tx,
+            reject_tx,
+            merge,
+            format,
+            checksum,
+            batch: String::with_capacity(BATCH_SIZE * 350),
+            count: 0,
+            rejects: String::new(),
+            fragments: FragmentCache::new(flow_limit),
+            leftovers: RunLeftovers::default(),
+        }
+    }
+
+    /// Process run `index` of the input line by line, then hand the assembly
+    /// thread the fragments of messages that continue beyond the run
+    fn process_run(&mut self, index: usize, run: &[u8]) {
+        for line_bytes in run.split(|&b| b == b'\n') {
+            self.process(line_bytes);
+        }
+        let mut leftovers = std::mem::take(&mut self.leftovers);
+        leftovers.index = index;
+        leftovers.trailing.extend(self.fragments.drain());
+        self.ml_tx.send(leftovers).unwrap();
     }
 
     #[inline]
@@ -494,40 +1862,90 @@ impl ExtractionState {
             line_bytes
         };
 
-        // Fast byte-level VDM check (no String allocation)
-        if !line_bytes.windows(3).any(|w| w == b"VDM") {
-            return;
-        }
+        // Fast byte-level sentence check (no String allocation): !<talker>VDM or VDO
+        let Some(address) = parse_sentence_address(line_bytes) else { return };
 
         // SAFETY: NMEA/AIS data is ASCII
         let sentence = unsafe { std::str::from_utf8_unchecked(line_bytes) };
-        let group = extract_digits_dashes_after(sentence, "g:");
+        let checksum_ok = line_checksums_ok(sentence);
+        if !checksum_ok {
+            match self.checksum {
+                ChecksumPolicy::Drop => return,
+                ChecksumPolicy::Flag => {}
+                ChecksumPolicy::Reject => {
+                    self.reject(sentence, Reject::Checksum);
+                    return;
+                }
+            }
+        }
+        let tags = TagBlock::parse(sentence);
+        let fragment = extract_fragment_fields(sentence, tags.group);
 
-        if group.is_empty() {
+        if fragment.0 <= 1 {
             // Fast path: single-line, zero intermediate allocations for types 1/2/3/18
-            append_single_line_json(sentence, &mut self.batch);
+            let result = append_single_line_json(sentence, address, &tags, checksum_ok, self.format, &mut self.batch);
+            let message_type = match result {
+                Ok(message_type) => message_type,
+                Err(reason) => {
+                    self.reject(sentence, reason);
+                    return;
+                }
+            };
             self.batch.push('\n');
             self.count += 1;
+            if self.merge.wants(message_type) {
+                if let Some(tx) = &self.static_tx {
+                    tx.send(cached_report(sentence, &tags, fragment, checksum_ok, "singleline")).unwrap();
+                }
+            }
             if self.count >= BATCH_SIZE {
                 self.flush();
             }
         } else {
-            // Build a minimal PositionReport for multiline caching
-            let mut parts = sentence.rsplitn(4, ',');
-            parts.next();
-            let raw_payload = parts.next().unwrap_or("").to_string();
-            let channel     = parts.next().unwrap_or("").to_string();
-            let partial = PositionReport {
-                landfall_time: extract_leading_digits(sentence).to_string(),
-                group: group.to_string(),
-                satellite_acquisition_time: extract_digits_after(sentence, "c:").to_string(),
-                source: extract_word_after(sentence, "s:").to_string(),
-                channel,
-                raw_payload,
-                message_class: "multiline".to_string(),
-                ..Default::default()
-            };
-            self.ml_tx.send(partial).unwrap();
+            self.add_fragment(cached_report(sentence, &tags, fragment, checksum_ok, "multiline"));
+        }
+    }
+
+    /// Assemble a multi-part message from the sentences in the current run.
+    /// Fragments it cannot assemble are left over for the assembly thread.
+    fn add_fragment(&mut self, line: PositionReport) {
+        let count = line.fragment_count;
+        if count > MAX_FRAGMENTS || line.fragment_number == 0 || line.fragment_number > count {
+            return;
+        }
+        let Some(key) = self.fragments.key(&line) else {
+            self.leftovers.orphans.push((line, "ambiguous"));
+            return;
+        };
+        if line.fragment_number == 1 {
+            self.leftovers.restarted.insert(key.clone());
+        } else if !self.leftovers.restarted.contains(&key) {
+            // The message started before this run
+            self.leftovers.leading.push(line);
+            return;
+        }
+        let (complete, superseded) = self.fragments.insert(key, line);
+        let orphans = &mut self.leftovers.orphans;
+        orphans.extend(superseded.into_iter().map(|fragment| (fragment, "superseded")));
+        orphans.extend(self.fragments.evict());
+        let Some(parts) = complete else { return };
+        let rejects = self.reject_tx.is_some().then_some(&mut self.rejects);
+        let static_tx = self.static_tx.as_ref();
+        if append_assembled_json(parts, self.format, self.merge, static_tx, &mut self.batch, rejects) {
+            self.count += 1;
+        }
+        if self.count >= BATCH_SIZE || self.rejects.len() >= BATCH_SIZE * 350 {
+            self.flush();
+        }
+    }
+
+    /// Queue a line for the reject file (dropped when there is none)
+    fn reject(&mut self, sentence: &str, reason: Reject) {
+        if self.reject_tx.is_some() {
+            append_reject_json(sentence, reason, &mut self.rejects);
+            if self.rejects.len() >= BATCH_SIZE * 350 {
+                self.flush();
+            }
         }
     }
 
@@ -537,7 +1955,65 @@ impl ExtractionState {
             self.batch = String::with_capacity(BATCH_SIZE * 350);
             self.count = 0;
         }
+        if let (Some(tx), false) = (&self.reject_tx, self.rejects.is_empty()) {
+            tx.send(std::mem::take(&mut self.rejects)).unwrap();
+        }
+    }
+}
+
+/// Build a minimal PositionReport (header fields + raw payload) for the
+/// multiline and static data merge caches
+fn cached_report(
+    sentence: &str,
+    tags: &TagBlock,
+    (fragment_count, fragment_number, fragment_id): (u8, u8, &str),
+    checksum_ok: bool,
+    message_class: &str,
+) -> PositionReport {
+    let mut parts = sentence.rsplitn(4, ',');
+    let fill_bits = extract_fill_bits(parts.next().unwrap_or(""));
+    let raw_payload = parts.next().unwrap_or("").to_string();
+    let channel     = parts.next().unwrap_or("").to_string();
+    PositionReport {
+        landfall_time: extract_leading_digits(sentence).to_string(),
+        group: tags.group.to_string(),
+        satellite_acquisition_time: tags.unix_seconds().to_string(),
+        source: tags.source.to_string(),
+        channel,
+        raw_payload,
+        message_class: message_class.to_string(),
+        fragment_count,
+        fragment_number,
+        fragment_id: fragment_id.to_string(),
+        raw_sentence: sentence.to_string(),
+        checksum_ok,
+        fill_bits,
+    }
+}
+
+/// Append one reject file line: the reason and the input line as received
+fn append_reject_json(sentence: &str, reason: Reject, out: &mut String) {
+    out.push_str("{\"reason\":");
+    match reason {
+        Reject::Checksum => push_safe_str(out, "checksum"),
+        Reject::PayloadCharacters => push_safe_str(out, "payload_characters"),
+        Reject::FillBits => push_safe_str(out, "fill_bits"),
+        Reject::PayloadLength { message_type, bits } => {
+            push_safe_str(out, "payload_length");
+            out.push_str(",\"message_type\":");
+            push_u64(out, message_type);
+            out.push_str(",\"bits\":");
+            push_u64(out, bits as u64);
+        }
+        Reject::MessageType { message_type } => {
+            push_safe_str(out, "message_type");
+            out.push_str(",\"message_type\":");
+            push_u64(out, message_type);
+        }
     }
+    out.push_str(",\"sentence\":");
+    push_escaped_str(out, sentence);
+    out.push_str("}\n");
 }
 
 /// Flush remaining batch on thread exit
@@ -545,6 +2021,280 @@ impl Drop for ExtractionState {
     fn drop(&mut self) { self.flush(); }
 }
 
+// ─── Multiline fragment cache ───────────────────────────────────────────────
+
+/// Identifies the fragments of one multi-part message. Tag block groups often
+/// carry `s:` only on their first sentence, so the key of a later sentence
+/// without a source comes from `FragmentCache::key`.
+#[derive(Clone, PartialEq, Eq, Hash)]
+struct FragmentKey {
+    source: String,
+    channel: String,
+    message_id: String,
+}
+
+impl FragmentKey {
+    fn new(line: &PositionReport) -> Self {
+        FragmentKey {
+            source: line.source.clone(),
+            channel: line.channel.clone(),
+            message_id: line.fragment_id.clone(),
+        }
+    }
+}
+
+/// The fragments a worker could not assemble within one run of the input.
+/// The assembly thread takes them in run order, so a message spanning any
+/// number of runs sees its sentences in input order.
+#[derive(Default)]
+struct RunLeftovers {
+    /// Position of the run in the input
+    index: usize,
+    /// Later fragments of messages that started before the run, in input order
+    leading: Vec<PositionReport>,
+    /// Keys the run started a message under, which end any message still
+    /// pending under them from earlier runs
+    restarted: HashSet<FragmentKey>,
+    /// Fragments of messages still incomplete at the end of the run
+    trailing: Vec<PositionReport>,
+    /// Fragments given up within the run: "superseded", "timeout" or "capacity"
+    orphans: Vec<(PositionReport, &'static str)>,
+}
+
+/// Split `data` into runs of about `size` bytes, each ending after a newline
+/// (or at the end of the input)
+fn line_runs(data: &[u8], size: usize) -> Vec<&[u8]> {
+    let mut runs = Vec::with_capacity(data.len() / size + 1);
+    let mut rest = data;
+    while !rest.is_empty() {
+        let end = match rest.get(size..).and_then(|tail| tail.iter().position(|&b| b == b'\n')) {
+            Some(newline) => size + newline + 1,
+            None => rest.len(),
+        };
+        let (run, tail) = rest.split_at(end);
+        runs.push(run);
+        rest = tail;
+    }
+    runs
+}
+
+/// An incomplete multi-part message waiting in the assembly cache
+#[derive(Default)]
+struct PendingMessage {
+    /// Fragments indexed by fragment number - 1
+    fragments: Vec<Option<PositionReport>>,
+    /// Reception time (epoch seconds) of the first fragment, when known
+    first_seen: Option<i64>,
+    /// Number of fragments the cache had received when this message started
+    arrival: u64,
+}
+
+/// Multi-part messages waiting for their remaining fragments. Eviction keeps
+/// the cache bounded when fragments never arrive.
+struct FragmentCache {
+    pending: HashMap<FragmentKey, PendingMessage>,
+    /// Sources of the pending messages by channel and message ID
+    sources: HashMap<(String, String), Vec<String>>,
+    /// Messages started more than this many fragments ago are evicted
+    flow_limit: u64,
+    /// Fragments added so far
+    received: u64,
+    /// Newest reception time seen, the clock for timeout eviction
+    newest: Option<i64>,
+}
+
+impl FragmentCache {
+    fn new(flow_limit: usize) -> Self {
+        FragmentCache {
+            pending: HashMap::new(),
+            sources: HashMap::new(),
+            flow_limit: flow_limit as u64,
+            received: 0,
+            newest: None,
+        }
+    }
+
+    /// The key of the message `line` belongs to. A later sentence without a
+    /// source belongs to the one message pending on its channel and message ID
+    /// whatever that message's source; None when several sources have one.
+    fn key(&self, line: &PositionReport) -> Option<FragmentKey> {
+        let mut key = FragmentKey::new(line);
+        if line.fragment_number == 1 || !line.source.is_empty() {
+            return Some(key);
+        }
+        match self.sources.get(&(key.channel.clone(), key.message_id.clone())).map(Vec::as_slice) {
+            Some([source]) => key.source.clone_from(source),
+            Some([_, _, ..]) => return None,
+            _ => {}
+        }
+        Some(key)
+    }
+
+    /// Forget the source of a message no longer pending under `key`
+    fn unindex(sources: &mut HashMap<(String, String), Vec<String>>, key: &FragmentKey) {
+        let group = (key.channel.clone(), key.message_id.clone());
+        if let Some(list) = sources.get_mut(&group) {
+            list.retain(|source| *source != key.source);
+            if list.is_empty() {
+                sources.remove(&group);
+            }
+        }
+    }
+
+    /// Remove the message pending under `key`, returning its fragments
+    fn remove(&mut self, key: &FragmentKey) -> Vec<PositionReport> {
+        let Some(pending) = self.pending.remove(key) else { return Vec::new() };
+        Self::unindex(&mut self.sources, key);
+        pending.fragments.into_iter().flatten().collect()
+    }
+
+    /// Add a fragment with a valid fragment number under its `key`. Returns the
+    /// fragments of the message it completes, in fragment order, and those of
+    /// an earlier message it superseded: when the key is reused (a repeated
+    /// fragment number or a different fragment count) before that message
+    /// completed, its fragments are rejected rather than stitched to the new
+    /// message.
+    fn insert(&mut self, key: FragmentKey, line: PositionReport) -> (Option<Vec<PositionReport>>, Vec<PositionReport>) {
+        self.received += 1;
+        let received_at = line.received_at().parse::<i64>().ok();
+        self.newest = self.newest.max(received_at);
+
+        let count = line.fragment_count as usize;
+        let slot = line.fragment_number as usize - 1;
+        let pending = self.pending.entry(key.clone()).or_insert_with(|| {
+            let group = (key.channel.clone(), key.message_id.clone());
+            self.sources.entry(group).or_default().push(key.source.clone());
+            PendingMessage::default()
+        });
+        let mut superseded = Vec::new();
+        if pending.fragments.len() != count || pending.fragments[slot].is_some() {
+            let fresh = PendingMessage {
+                fragments: vec![None; count],
+                first_seen: received_at,
+                arrival: self.received,
+            };
+            superseded.extend(std::mem::replace(pending, fresh).fragments.into_iter().flatten());
+        }
+        pending.fragments[slot] = Some(line);
+        if pending.fragments.iter().any(Option::is_none) {
+            return (None, superseded);
+        }
+        (Some(self.remove(&key)), superseded)
+    }
+
+    /// Every `EVICTION_INTERVAL` fragments, remove the messages that timed out
+    /// or were started more than `flow_limit` fragments ago. Returns their
+    /// fragments with the reason, "timeout" or "capacity".
+    fn evict(&mut self) -> Vec<(PositionReport, &'static str)> {
+        let mut evicted = Vec::new();
+        if !self.received.is_multiple_of(EVICTION_INTERVAL) {
+            return evicted;
+        }
+        let (newest, received, flow_limit) = (self.newest, self.received, self.flow_limit);
+        let sources = &mut self.sources;
+        self.pending.retain(|key, pending| {
+            let timed_out = matches!((pending.first_seen, newest),
+                (Some(first), Some(now)) if now - first > FRAGMENT_TIMEOUT_SECS);
+            if !timed_out && pending.arrival + flow_limit > received {
+                return true;
+            }
+            let reason = if timed_out { "timeout" } else { "capacity" };
+            Self::unindex(sources, key);
+            let fragments = std::mem::take(&mut pending.fragments);
+            evicted.extend(fragments.into_iter().flatten().map(|fragment| (fragment, reason)));
+            false
+        });
+        evicted
+    }
+
+    /// Remove and return the fragments of every incomplete message
+    fn drain(&mut self) -> impl Iterator<Item = PositionReport> + '_ {
+        self.sources.clear();
+        self.pending.drain().flat_map(|(_, pending)| pending.fragments.into_iter().flatten())
+    }
+}
+
+/// Join the fragments of a complete message, in fragment order, into one
+/// report: header fields from the last sentence, acquisition time and the
+/// other tag block fields from the first, and the source from the first that
+/// carries one. Fails, handing the fragments back, when the joined payload
+/// cannot be decoded.
+fn assemble_fragments(mut parts: Vec<PositionReport>) -> Result<PositionReport, (Reject, Vec<PositionReport>)> {
+    let raw_payload: String = parts.iter().map(|part| part.raw_payload.as_str()).collect();
+    // The fill bits of the last fragment apply to the assembled payload
+    let fill_bits = parts.last().and_then(|part| part.fill_bits);
+    let payload = Payload::from_str(&raw_payload);
+    if let Err(reason) = validate_payload(&raw_payload, payload.as_slice(), fill_bits) {
+        return Err((reason, parts));
+    }
+    let sat_time = std::mem::take(&mut parts[0].satellite_acquisition_time);
+    let first_sentence = std::mem::take(&mut parts[0].raw_sentence);
+    let checksum_ok = parts.iter().all(|part| part.checksum_ok);
+    let source = parts.iter_mut().map(|part| std::mem::take(&mut part.source)).find(|source| !source.is_empty());
+    let mut line = parts.pop().unwrap();
+    line.source = source.unwrap_or_default();
+    line.raw_payload = raw_payload;
+    line.satellite_acquisition_time = sat_time;
+    line.raw_sentence = first_sentence;
+    line.checksum_ok = checksum_ok;
+    Ok(line)
+}
+
+/// Write a complete multi-part message to `out` and forward it to the static
+/// data merge when wanted. When its payload cannot be decoded, each fragment
+/// goes to `rejects` instead (if given) and false is returned.
+fn append_assembled_json(
+    parts: Vec<PositionReport>,
+    format: OutputFormat,
+    merge: StaticMerge,
+    static_tx: Option<&Sender<PositionReport>>,
+    out: &mut String,
+    rejects: Option<&mut String>,
+) -> bool {
+    match assemble_fragments(parts) {
+        Ok(line) => {
+            let message_type = append_report_json(&line, format, out);
+            out.push('\n');
+            if merge.wants(message_type) {
+                if let Some(tx) = static_tx {
+                    tx.send(line).unwrap();
+                }
+            }
+            true
+        }
+        Err((reason, parts)) => {
+            if let Some(rejects) = rejects {
+                for part in &parts {
+                    append_reject_json(&part.raw_sentence, reason, rejects);
+                }
+            }
+            false
+        }
+    }
+}
+
+/// Append one orphan report line for a fragment of a message that never
+/// completed. `reason` is "superseded", "timeout", "capacity" or "incomplete".
+fn append_orphan_json(fragment: &PositionReport, reason: &str, out: &mut String) {
+    out.push_str("{\"reason\":");
+    push_safe_str(out, reason);
+    out.push_str(",\"source\":");
+    push_escaped_str(out, &fragment.source);
+    out.push_str(",\"channel\":");
+    push_escaped_str(out, &fragment.channel);
+    out.push_str(",\"message_id\":");
+    push_escaped_str(out, &fragment.fragment_id);
+    out.push_str(",\"fragment_count\":");
+    push_u64(out, fragment.fragment_count as u64);
+    out.push_str(",\"fragment_number\":");
+    push_u64(out, fragment.fragment_number as u64);
+    out.push_str(",\"received_at\":");
+    push_safe_str(out, fragment.received_at());
+    out.push_str(",\"sentence\":");
+    push_escaped_str(out, &fragment.raw_sentence);
+    out.push_str("}\n");
+}
+
 // ─── main ────────────────────────────────────────────────────────────────────
 
 fn main() {
@@ -560,12 +2310,43 @@ fn main() {
         .arg(Arg::new("FLOW_LIMIT").help("Max objects in memory (default: 500000)").takes_value(true).index(3))
         .arg(Arg::new("PARSE_THREADS").help("Parse thread count (default: CPUs)").takes_value(true).index(4))
         .arg(Arg::new("MULTILINE_THREADS").help("(unused)").takes_value(true).index(5))
+        .arg(Arg::new("MERGE_CLASS_B").long("merge-class-b")
+            .help("Also emit a combined Class B static record once both type 24 parts of an MMSI are seen"))
+        .arg(Arg::new("JOIN_INLAND").long("join-inland")
+            .help("Also emit a type 5 record joined with inland static data (DAC 200, FI 10) of the same MMSI"))
+        .arg(Arg::new("PHYSICAL_UNITS").long("physical-units")
+            .help("Write SOG in knots, COG/heading in degrees and ROT in degrees/minute, with \"not available\" values as null"))
+        .arg(Arg::new("SCHEMA").long("schema").takes_value(true).possible_values(["1", "2"])
+            .help("Output schema: 1 = legacy string-typed columns (default), 2 = typed fields with schema_version"))
+        .arg(Arg::new("CHECKSUM").long("checksum").takes_value(true)
+            .possible_values(["drop", "flag", "reject"]).requires_if("reject", "REJECT_FILE")
+            .help("Lines with a bad sentence or tag block checksum: drop them (default), flag them with checksum_ok, or write them to the reject file"))
+        .arg(Arg::new("REJECT_FILE").long("reject-file").takes_value(true).value_name("FILE")
+            .help("Write rejected lines (checksum failures with --checksum reject, undecodable payloads) to FILE"))
+        .arg(Arg::new("ORPHANS").long("orphans").takes_value(true).value_name("FILE")
+            .help("Write the fragments of multi-part messages that never completed to FILE"))
         .get_matches();
 
     let input_file  = matches.value_of("INPUT").unwrap_or("").to_string();
     let output_file = matches.value_of("OUTPUT").unwrap_or("").to_string();
     let flow_limit: usize = matches.value_of("FLOW_LIMIT")
         .and_then(|v| v.parse().ok()).unwrap_or(500_000);
+    let orphan_file = matches.value_of("ORPHANS").map(str::to_string);
+    let merge = StaticMerge {
+        class_b: matches.is_present("MERGE_CLASS_B"),
+        inland:  matches.is_present("JOIN_INLAND"),
+    };
+    let format = OutputFormat {
+        physical_units: matches.is_present("PHYSICAL_UNITS"),
+        schema: if matches.value_of("SCHEMA") == Some("2") { Schema::V2 } else { Schema::Legacy },
+        checksum_field: matches.value_of("CHECKSUM") == Some("flag"),
+    };
+    let checksum = match matches.value_of("CHECKSUM") {
+        Some("flag") => ChecksumPolicy::Flag,
+        Some("reject") => ChecksumPolicy::Reject,
+        _ => ChecksumPolicy::Drop,
+    };
+    let reject_file = matches.value_of("REJECT_FILE").map(str::to_string);
 
     if let Some(t) = matches.value_of("PARSE_THREADS").and_then(|v| v.parse::<usize>().ok()) {
         rayon::ThreadPoolBuilder::new().num_threads(t).build_global().ok();
@@ -573,56 +2354,185 @@ fn main() {
 
     let batch_limit = flow_limit / BATCH_SIZE + 256;
 
-    // multiline channel: partial PositionReports
-    let (ml_tx, ml_rx) = bounded::<PositionReport>(flow_limit);
+    // multiline channel: fragments the workers could not assemble, per run
+    let (ml_tx, ml_rx) = bounded::<RunLeftovers>(batch_limit);
     // output channel: pre-concatenated batches of JSON lines
     let (out_tx, out_rx) = bounded::<String>(batch_limit);
 
+    // ── Static data merge thread (optional) ──────────────────────────────────
+    let (static_tx, static_rx) = bounded::<PositionReport>(flow_limit);
+    let static_tx = merge.enabled().then_some(static_tx);
+    let static_out_tx = out_tx.clone();
+    let static_thread = thread::spawn(move || {
+        // Latest halves seen for each MMSI that is still waiting for its counterpart:
+        // type 24 (part A, part B) and (type 5, inland DAC 200 FI 10)
+        let mut class_b: HashMap<u64, (Option<PositionReport>, Option<PositionReport>)> = HashMap::new();
+        let mut inland:  HashMap<u64, (Option<PositionReport>, Option<PositionReport>)> = HashMap::new();
+        let mut batch = String::with_capacity(BATCH_SIZE * 350);
+
+        for line in static_rx {
+            let payload = Payload::from_str(&line.raw_payload);
+            let pv = payload.as_slice();
+            let mmsi = pick_u64(pv, 8, 30);
+
+            match pick_u64(pv, 0, 6) {
+                24 => {
+                    // Part numbers 2 and 3 are reserved
+                    let part = pick_u64(pv, 38, 2);
+                    if part > 1 {
+                        continue;
+                    }
+                    let entry = class_b.entry(mmsi).or_default();
+                    if part == 0 { entry.0 = Some(line) } else { entry.1 = Some(line) }
+                    if let (Some(part_a), Some(part_b)) = entry {
+                        append_class_b_static_json(part_a, part_b, format, &mut batch);
+                        batch.push('\n');
+                        class_b.remove(&mmsi);
+                    }
+                }
+                5 => {
+                    let entry = inland.entry(mmsi).or_default();
+                    entry.0 = Some(line);
+                    if let (Some(static_report), Some(inland_report)) = entry {
+                        append_inland_static_json(static_report, inland_report, format, &mut batch);
+                        batch.push('\n');
+                        inland.remove(&mmsi);
+                    }
+                }
+                8 if pick_u64(pv, 40, 10) == 200 && pick_u64(pv, 50, 6) == 10
+                    && line.bit_len() >= 56 + INLAND_STATIC_BITS => {
+                    let entry = inland.entry(mmsi).or_default();
+                    entry.1 = Some(line);
+                    if let (Some(static_report), Some(inland_report)) = entry {
+                        append_inland_static_json(static_report, inland_report, format, &mut batch);
+                        batch.push('\n');
+                        inland.remove(&mmsi);
+                    }
+                }
+                _ => continue,
+            }
+
+            if batch.len() >= BATCH_SIZE * 350 {
+                static_out_tx.send(std::mem::take(&mut batch)).unwrap();
+                batch = String::with_capacity(BATCH_SIZE * 350);
+            }
+        }
+        if !batch.is_empty() {
+            static_out_tx.send(batch).unwrap();
+        }
+    });
+
+    // ── Reject file writer (optional) ─────────────────────────────────────────
+    let (reject_tx, reject_rx) = bounded::<String>(batch_limit);
+    let reject_tx = reject_file.is_some().then_some(reject_tx);
+    let reject_writer = thread::spawn(move || {
+        let Some(path) = reject_file else { return };
+        let mut buf = BufWriter::new(File::create(path).expect("cannot create reject file"));
+        let mut total: u64 = 0;
+        for batch in reject_rx {
+            total += batch.bytes().filter(|&b| b == b'\n').count() as u64;
+            buf.write_all(batch.as_bytes()).unwrap();
+        }
+        buf.flush().unwrap();
+        eprintln!("Rejects done: {} lines", total);
+    });
+
     // ── Multiline assembly thread ─────────────────────────────────────────────
     let ml_out_tx = out_tx.clone();
+    let ml_static_tx = static_tx.clone();
+    let ml_reject_tx = reject_tx.clone();
     let ml_thread = thread::spawn(move || {
-        let mut payload_cache:  HashMap<String, String> = HashMap::new();
-        let mut source_cache:   HashMap<String, String> = HashMap::new();
-        let mut sat_time_cache: HashMap<String, String> = HashMap::new();
+        // Messages spanning two or more runs of the input
+        let mut fragment_cache = FragmentCache::new(flow_limit);
+        // Leftovers of runs that arrived ahead of an earlier run
+        let mut waiting: BTreeMap<usize, RunLeftovers> = BTreeMap::new();
+        let mut next_run = 0;
+        let mut orphans = orphan_file.map(|path| {
+            BufWriter::new(File::create(path).expect("cannot create orphan file"))
+        });
+        let mut orphan_batch = String::new();
+        let (mut rejected, mut evicted, mut incomplete) = (0u64, 0u64, 0u64);
+        let mut orphan = |fragment: &PositionReport, reason: &str| {
+            match reason {
+                "superseded" | "ambiguous" => rejected += 1,
+                "incomplete" => incomplete += 1,
+                _ => evicted += 1,
+            }
+            if let Some(file) = &mut orphans {
+                append_orphan_json(fragment, reason, &mut orphan_batch);
+                if orphan_batch.len() >= BATCH_SIZE * 350 {
+                    file.write_all(orphan_batch.as_bytes()).unwrap();
+                    orphan_batch.clear();
+                }
+            }
+        };
         let mut batch = String::with_capacity(BATCH_SIZE * 350);
+        let mut reject_batch = String::new();
 
         // Iterates until all ml_tx senders are dropped (channel closed)
-        for mut line in ml_rx {
-            payload_cache.insert(line.group.clone(), line.raw_payload.clone());
-            if !line.satellite_acquisition_time.is_empty() {
-                sat_time_cache.insert(line.group.clone(), line.satellite_acquisition_time);
-            }
-            if !line.source.is_empty() {
-                source_cache.insert(line.group.clone(), line.source);
-            }
-
-            let part1 = format!("1-2-{}", last_four_characters(&line.group));
-            let part2 = format!("2-2-{}", last_four_characters(&line.group));
-
-            if payload_cache.contains_key(&part1) && payload_cache.contains_key(&part2) {
-                line.raw_payload = format!(
-                    "{}{}",
-                    payload_cache.remove(&part1).unwrap(),
-                    payload_cache.remove(&part2).unwrap()
-                );
-                line.satellite_acquisition_time =
-                    sat_time_cache.remove(&part1).unwrap_or_default();
-                line.source = source_cache.remove(&part1).unwrap_or_default();
-
-                line = decode_payload(line);
-                append_report_json(&line, &mut batch);
-                batch.push('\n');
-                if batch.len() >= BATCH_SIZE * 350 {
-                    ml_out_tx.send(std::mem::take(&mut batch)).unwrap();
-                    batch = String::with_capacity(BATCH_SIZE * 350);
+        for leftovers in ml_rx {
+            waiting.insert(leftovers.index, leftovers);
+            while let Some(run) = waiting.remove(&next_run) {
+                next_run += 1;
+                for (fragment, reason) in &run.orphans {
+                    orphan(fragment, reason);
                 }
+                // Fragments continuing earlier runs' messages, then (None) the
+                // keys the run restarted, then the messages it leaves for later runs
+                let leading = run.leading.into_iter().map(Some);
+                let trailing = run.trailing.into_iter().map(Some);
+                for line in leading.chain([None]).chain(trailing) {
+                    let Some(line) = line else {
+                        for key in &run.restarted {
+                            for fragment in &fragment_cache.remove(key) {
+                                orphan(fragment, "superseded");
+                            }
+                        }
+                        continue;
+                    };
+                    let Some(key) = fragment_cache.key(&line) else {
+                        orphan(&line, "ambiguous");
+                        continue;
+                    };
+                    let (complete, superseded) = fragment_cache.insert(key, line);
+                    for fragment in &superseded {
+                        orphan(fragment, "superseded");
+                    }
+                    for (fragment, reason) in &fragment_cache.evict() {
+                        orphan(fragment, reason);
+                    }
+                    let Some(parts) = complete else { continue };
+                    let rejects = ml_reject_tx.is_some().then_some(&mut reject_batch);
+                    append_assembled_json(parts, format, merge, ml_static_tx.as_ref(), &mut batch, rejects);
+                }
+            }
+            if batch.len() >= BATCH_SIZE * 350 {
+                ml_out_tx.send(std::mem::take(&mut batch)).unwrap();
+                batch = String::with_capacity(BATCH_SIZE * 350);
+            }
+            if let (Some(tx), true) = (&ml_reject_tx, reject_batch.len() >= BATCH_SIZE * 350) {
+                tx.send(std::mem::take(&mut reject_batch)).unwrap();
             }
         }
         if !batch.is_empty() {
             ml_out_tx.send(batch).unwrap();
         }
-        println!("Multiline assembly done");
-        // ml_out_tx dropped here → one less out_tx clone
+        if let (Some(tx), false) = (&ml_reject_tx, reject_batch.is_empty()) {
+            tx.send(reject_batch).unwrap();
+        }
+        // Whatever is left never completed
+        for fragment in fragment_cache.drain() {
+            orphan(&fragment, "incomplete");
+        }
+        if let Some(mut file) = orphans {
+            file.write_all(orphan_batch.as_bytes()).unwrap();
+            file.flush().unwrap();
+        }
+        println!(
+            "Multiline assembly done ({} fragments rejected, {} evicted, {} incomplete)",
+            rejected, evicted, incomplete
+        );
+        // ml_out_tx, ml_reject_tx dropped here → one less clone of each
     });
 
     // ── Writer thread (64 MB buffer, processes pre-concatenated batches) ──────
@@ -644,21 +2554,357 @@ fn main() {
         eprintln!("Writer done: {} total lines", total);
     });
 
-    // ── Memory-map the input and process all lines in parallel with Rayon ─────
+    // ── Memory-map the input and process its runs in parallel with Rayon ──────
     let file = File::open(&input_file).expect("file not found");
     let mmap = unsafe { Mmap::map(&file).expect("mmap failed") };
     let data: &[u8] = &mmap;
 
-    data.par_split(|&b| b == b'\n')
+    line_runs(data, RUN_BYTES)
+        .into_par_iter()
+        .enumerate()
         .for_each_with(
-            ExtractionState::new(out_tx.clone(), ml_tx.clone()),
-            |state, line_bytes| state.process(line_bytes),
+            ExtractionState::new(
+                out_tx.clone(),
+                ml_tx.clone(),
+                static_tx.clone(),
+                reject_tx.clone(),
+                merge,
+                format,
+                checksum,
+                flow_limit,
+            ),
+            |state, (index, run)| state.process_run(index, run),
         );
 
     // Signal channels: drop our sender copies so threads know we're done
-    drop(ml_tx);  // → ml_rx channel closes → ml_thread exits → ml_out_tx drops
+    drop(ml_tx);  // → ml_rx channel closes → ml_thread exits → ml_out_tx, ml_static_tx drop
+    drop(static_tx); // → with ml_static_tx gone, static_rx closes → static_thread exits
     drop(out_tx); // → combined with ExtractionState/ml drops → out_rx closes → writer exits
+    drop(reject_tx); // → reject_rx closes → reject writer exits
 
     ml_thread.join().unwrap();
+    static_thread.join().unwrap();
     writer.join().unwrap();
+    reject_writer.join().unwrap();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Armor `(width, value)` fields, zero-padded to `bits`, into a payload
+    /// and its fill bits
+    fn armor(fields: &[(usize, u64)], bits: usize) -> (String, u8) {
+        let mut all: Vec<u8> = fields.iter()
+            .flat_map(|&(width, value)| (0..width).rev().map(move |i| ((value >> i) & 1) as u8))
+            .collect();
+        all.resize(bits, 0);
+        let fill = (6 - bits % 6) % 6;
+        all.resize(bits + fill, 0);
+        let payload = all.chunks(6)
+            .map(|chunk| {
+                let v = chunk.iter().fold(0, |acc, &b| acc << 1 | b);
+                (if v < 40 { v + 48 } else { v + 56 }) as char
+            })
+            .collect();
+        (payload, fill as u8)
+    }
+
+    /// Validate a `bits`-long payload of `message_type` beginning with `fields`
+    /// (after the type, repeat indicator and MMSI)
+    fn validate(message_type: u64, fields: &[(usize, u64)], bits: usize) -> Result<(u64, usize), Reject> {
+        let mut all = vec![(6, message_type), (2, 0), (30, 244_000_000)];
+        all.extend_from_slice(fields);
+        let (raw, fill) = armor(&all, bits);
+        validate_payload(&raw, Payload::from_str(&raw).as_slice(), Some(fill))
+    }
+
+    fn length_ok(message_type: u64, fields: &[(usize, u64)], bits: usize) -> bool {
+        match validate(message_type, fields, bits) {
+            Ok(valid) => valid == (message_type, bits),
+            Err(Reject::PayloadLength { message_type: t, bits: b }) => {
+                assert_eq!((t, b), (message_type, bits));
+                false
+            }
+            Err(other) => panic!("type {} at {} bits: {:?}", message_type, bits, other),
+        }
+    }
+
+    #[test]
+    fn payload_lengths_follow_the_message_type() {
+        for bits in [162, 174] {
+            assert!(!length_ok(1, &[], bits));
+        }
+        assert!(length_ok(1, &[], 168));
+        assert!(!length_ok(5, &[], 419));
+        assert!(length_ok(5, &[], 420));
+        assert!(length_ok(5, &[], 432));
+        assert!(!length_ok(5, &[], 433));
+        assert!(!length_ok(21, &[], 271));
+        assert!(length_ok(21, &[], 272));
+        assert!(length_ok(21, &[], 360));
+        assert!(!length_ok(21, &[], 361));
+        assert!(length_ok(7, &[], 72));
+        assert!(!length_ok(7, &[], 88));
+        assert!(length_ok(27, &[], 96));
+        assert!(!length_ok(27, &[], 168));
+        // Part A of type 24 may omit its spare bits; part B may not
+        assert!(length_ok(24, &[(2, 0)], 160));
+        assert!(length_ok(24, &[(2, 1)], 168));
+        assert!(!length_ok(24, &[(2, 1)], 160));
+    }
+
+    #[test]
+    fn undefined_message_types_are_rejected() {
+        for message_type in [0, 28, 63] {
+            assert!(matches!(validate(message_type, &[], 168),
+                Err(Reject::MessageType { message_type: t }) if t == message_type));
+        }
+    }
+
+    #[test]
+    fn fill_bits_are_taken_off_the_payload_length() {
+        let (raw, _) = armor(&[(6, 1)], 168);
+        let pv = Payload::from_str(&raw);
+        assert!(matches!(validate_payload(&raw, pv.as_slice(), Some(0)), Ok((1, 168))));
+        assert!(matches!(validate_payload(&raw, pv.as_slice(), Some(2)),
+            Err(Reject::PayloadLength { message_type: 1, bits: 166 })));
+        assert!(matches!(validate_payload(&raw, pv.as_slice(), None), Err(Reject::FillBits)));
+        assert!(matches!(validate_payload("1", Payload::from_str("1").as_slice(), Some(5)),
+            Err(Reject::PayloadLength { bits: 1, .. })));
+        assert_eq!(extract_fill_bits("2*3C"), Some(2));
+        assert_eq!(extract_fill_bits("6*3C"), None);
+        assert_eq!(extract_fill_bits(""), None);
+        assert!(matches!(validate_payload("1!", Payload::from_str("1!").as_slice(), Some(0)),
+            Err(Reject::PayloadCharacters)));
+    }
+
+    #[test]
+    fn slot_binary_minimums_follow_the_addressed_and_structured_flags() {
+        // (addressed, structured) -> header bits
+        for (flags, header) in [((0, 0), 40), ((1, 0), 70), ((0, 1), 56), ((1, 1), 86)] {
+            let fields = [(1, flags.0), (1, flags.1)];
+            assert!(!length_ok(25, &fields, header - 1), "{:?}", flags);
+            assert!(length_ok(25, &fields, header), "{:?}", flags);
+            assert!(length_ok(25, &fields, 168), "{:?}", flags);
+            assert!(!length_ok(25, &fields, 169), "{:?}", flags);
+            assert!(!length_ok(26, &fields, header + 19), "{:?}", flags);
+            assert!(length_ok(26, &fields, header + 20), "{:?}", flags);
+            assert!(length_ok(26, &fields, 1064), "{:?}", flags);
+        }
+    }
+
+    #[test]
+    fn registered_applications_need_their_full_data() {
+        // Type 8 meteorological and hydrographic data (1, 31) and inland
+        // static data (200, 10), after the 56-bit header
+        for (dac, fi, data_bits) in [(1, 31, MET_HYDRO_BITS), (200, 10, INLAND_STATIC_BITS)] {
+            let fields = [(2, 0), (10, dac), (6, fi)];
+            assert!(!length_ok(8, &fields, 56 + data_bits - 1), "({}, {})", dac, fi);
+            assert!(length_ok(8, &fields, 56 + data_bits), "({}, {})", dac, fi);
+        }
+        // Unregistered applications only need the header
+        assert!(length_ok(8, &[(2, 0), (10, 1), (6, 11)], 56));
+        assert!(length_ok(6, &[(2, 0), (30, 0), (2, 0), (10, 1), (6, 11)], 88));
+
+        // Type 6 carries the DAC after its 72-bit addressed header
+        let (raw, _) = armor(&[(6, 6), (2, 0), (30, 244_000_000), (2, 0), (30, 0), (2, 0), (10, 235), (6, 10)], 88);
+        let pv = Payload::from_str(&raw);
+        let registry: &[BinaryApplication] = &[((235, 10), 32, append_inland_static_fields)];
+        assert!(!application_length_valid(pv.as_slice(), registry, 72, 88 + 31));
+        assert!(application_length_valid(pv.as_slice(), registry, 72, 88 + 32));
+        assert!(application_length_valid(pv.as_slice(), &[], 72, 88));
+    }
+
+    #[test]
+    fn civil_dates_round_trip_through_day_counts() {
+        assert_eq!(days_from_civil(1970, 1, 1), 0);
+        assert_eq!(days_from_civil(2000, 3, 1), 11_017);
+        assert_eq!(days_from_civil(1969, 12, 31), -1);
+        for days in [-1, 0, 11_016, 11_017, 19_358, 19_782] {
+            let (year, month, day) = civil_from_days(days);
+            assert_eq!(days_from_civil(year, month, day), days);
+        }
+        assert_eq!(civil_from_days(19_782), (2024, 2, 29));
+        assert_eq!(days_in_month(2023, 2), 28);
+        assert_eq!(days_in_month(2024, 2), 29);
+        assert_eq!(days_in_month(1900, 2), 28);
+        assert_eq!(days_in_month(2000, 2), 29);
+        assert_eq!(days_in_month(2023, 4), 30);
+        assert_eq!(days_in_month(2023, 12), 31);
+    }
+
+    #[test]
+    fn eta_year_is_the_one_closest_to_reception() {
+        // Received 2023-01-02 00:00 UTC
+        let received = 1_672_617_600;
+        assert_eq!(resolve_eta(received, 12, 31, 12, 0), Some(1_672_488_000));
+        assert_eq!(resolve_eta(received, 1, 3, 6, 30), Some(1_672_727_400));
+        // Received 2022-12-30 00:00 UTC, due early in the new year
+        assert_eq!(resolve_eta(1_672_358_400, 1, 2, 0, 0), Some(1_672_617_600));
+    }
+
+    #[test]
+    fn eta_on_a_leap_day_lands_in_a_leap_year() {
+        // Received 2024-02-20 and 2023-02-20: both resolve to 2024-02-29
+        assert_eq!(resolve_eta(1_708_387_200, 2, 29, 0, 0), Some(1_709_164_800));
+        assert_eq!(resolve_eta(1_676_851_200, 2, 29, 0, 0), Some(1_709_164_800));
+    }
+
+    #[test]
+    fn invalid_or_unavailable_etas_do_not_resolve() {
+        let received = 1_672_617_600;
+        assert_eq!(resolve_eta(received, 2, 30, 12, 0), None);
+        assert_eq!(resolve_eta(received, 4, 31, 12, 0), None);
+        assert_eq!(resolve_eta(received, 0, 15, 12, 0), None);
+        assert_eq!(resolve_eta(received, 13, 15, 12, 0), None);
+        assert_eq!(resolve_eta(received, 6, 0, 12, 0), None);
+        assert_eq!(resolve_eta(received, 6, 15, 24, 0), None);
+        assert_eq!(resolve_eta(received, 6, 15, 12, 60), None);
+    }
+
+    /// `body` with its checksum in the given hex case
+    fn with_checksum(body: &str, upper: bool) -> String {
+        let sum = body.bytes().fold(0u8, |acc, b| acc ^ b);
+        if upper { format!("{}*{:02X}", body, sum) } else { format!("{}*{:02x}", body, sum) }
+    }
+
+    #[test]
+    fn sentence_checksums_are_checked() {
+        let body = "AIVDM,1,1,,A,13u?etPv2;0n:dDPwUM1U1Cb069D,0";
+        assert_eq!(with_checksum(body, true), format!("{}*24", body));
+        assert!(checksum_matches(&with_checksum(body, true)));
+        assert!(!checksum_matches(&format!("{}*25", body)));
+        assert!(!checksum_matches(body));
+        assert!(!checksum_matches(&format!("{}*2", body)));
+        // A sign is not a hex digit
+        assert!(!checksum_matches("A@*+1"));
+        assert!(checksum_matches("A@*01"));
+    }
+
+    #[test]
+    fn lowercase_checksums_are_accepted() {
+        let body = "AIVDM,1,1,,B,B52K>;h00Fc>jpUlNV@ikwpUoP06,0";
+        let lower = with_checksum(body, false);
+        assert!(lower.bytes().any(|c| c.is_ascii_lowercase()), "{}", lower);
+        assert!(checksum_matches(&lower));
+        assert!(line_checksums_ok(&format!("!{}", lower)));
+    }
+
+    #[test]
+    fn every_tag_block_checksum_is_checked() {
+        let sentence = format!("!{}", with_checksum("AIVDM,1,1,,A,13u?etPv2;0n:dDPwUM1U1Cb069D,0", true));
+        let tags = format!("\\{}\\", with_checksum("s:sat1,c:1643588420", true));
+        let group = format!("\\{}\\", with_checksum("g:1-1-1234", false));
+        assert!(line_checksums_ok(&sentence));
+        assert!(line_checksums_ok(&format!("{}{}", tags, sentence)));
+        assert!(line_checksums_ok(&format!("1643588425{}{}{}", tags, group, sentence)));
+        assert!(!line_checksums_ok(&format!("\\s:sat1,c:1643588420*00\\{}", sentence)));
+        assert!(!line_checksums_ok(&format!("{}\\g:1-1-1234*00\\{}", tags, sentence)));
+        assert!(!line_checksums_ok(&format!("{}{}", tags, &sentence[..sentence.len() - 1])));
+        assert!(!line_checksums_ok("no sentence here"));
+    }
+
+    const SENTENCE: &str = "!AIVDM,1,1,,A,13u?etPv2;0n:dDPwUM1U1Cb069D,0*24";
+
+    #[test]
+    fn tag_block_time_is_seconds_or_truncated_milliseconds() {
+        let seconds = |c: &str| TagBlock::parse(&format!("\\c:{}*00\\{}", c, SENTENCE)).unix_seconds().to_string();
+        assert_eq!(seconds("1643588400"), "1643588400");
+        assert_eq!(seconds("1643588400123"), "1643588400");
+        // 11 digits are still seconds; from 12 digits on they are milliseconds
+        assert_eq!(seconds("99999999999"), "99999999999");
+        assert_eq!(seconds("999999999999"), "999999999");
+        assert_eq!(seconds("1643588400.5"), "1643588400");
+        assert_eq!(seconds(""), "");
+        assert_eq!(TagBlock::parse(SENTENCE).unix_seconds(), "");
+    }
+
+    #[test]
+    fn tag_block_fields_are_read_from_every_block() {
+        let line = format!(
+            "1643588425\\g:1-2-1234,s:sat1,c:1643588400*00\\\\n:42,r:17,d:dest,t:some text,i:info*00\\{}",
+            SENTENCE
+        );
+        let tags = TagBlock::parse(&line);
+        assert_eq!(tags.group, "1-2-1234");
+        assert_eq!(tags.source, "sat1");
+        assert_eq!(tags.unix_time, "1643588400");
+        assert_eq!(tags.line_count, "42");
+        assert_eq!(tags.relative_time, "17");
+        assert_eq!(tags.destination, "dest");
+        assert_eq!(tags.text, "some text");
+        assert_eq!(tags.info, "info");
+        assert!(tags.other.is_empty());
+    }
+
+    #[test]
+    fn unknown_tag_block_keys_are_kept_apart() {
+        let line = format!("\\s:sat1,x:1,q:two*00\\{}", SENTENCE);
+        let tags = TagBlock::parse(&line);
+        assert_eq!(tags.source, "sat1");
+        assert_eq!(tags.other.into_iter().collect::<Vec<_>>(), [("q", "two"), ("x", "1")]);
+    }
+
+    #[test]
+    fn malformed_tag_blocks_yield_what_they_can() {
+        // No checksum: the whole block is the body
+        assert_eq!(TagBlock::parse(&format!("\\s:sat1\\{}", SENTENCE)).source, "sat1");
+        // Fields without a colon are skipped; a value may contain one
+        let line = format!("\\s,t:a:b,:x*00\\{}", SENTENCE);
+        let tags = TagBlock::parse(&line);
+        assert_eq!((tags.source, tags.text), ("", "a:b"));
+        assert_eq!(tags.other.into_iter().collect::<Vec<_>>(), [("", "x")]);
+        // Empty blocks and a line without a sentence give nothing
+        let line = format!("\\\\{}", SENTENCE);
+        let tags = TagBlock::parse(&line);
+        assert!(tags.source.is_empty() && tags.group.is_empty() && tags.other.is_empty());
+        let tags = TagBlock::parse("\\s:sat1*00\\");
+        assert!(tags.source.is_empty());
+        // A later field of the same key wins
+        assert_eq!(TagBlock::parse(&format!("\\s:a,s:b*00\\{}", SENTENCE)).source, "b");
+    }
+
+    fn header<'a>(landfall_time: &'a str, satellite_acquisition_time: &'a str) -> Header<'a> {
+        Header {
+            landfall_time,
+            group: "",
+            satellite_acquisition_time,
+            source: "",
+            channel: "A",
+            raw_payload: "",
+            message_class: "singleline",
+            checksum_ok: true,
+            address: ("AI", false),
+            tags: TagBlock::default(),
+        }
+    }
+
+    #[test]
+    fn typed_header_times_are_valid_json_numbers() {
+        let format = OutputFormat { schema: Schema::V2, ..OutputFormat::default() };
+        let mut out = String::new();
+        begin_record(&mut out, &header("0001643588425", "99999999999999999999"), 1, format).end();
+        assert!(out.contains(",\"landfall_time\":1643588425,"), "{}", out);
+        assert!(!out.contains("satellite_acquisition_time"), "{}", out);
+    }
+
+    #[test]
+    fn unavailable_values_are_empty_strings_in_the_legacy_layout() {
+        for (format, expected) in [
+            (OutputFormat::default(), r#""eta_day":"","loaded":"","draught":"","eta_month":"3","dte":"1"}"#),
+            (OutputFormat { schema: Schema::V2, ..OutputFormat::default() },
+                r#""eta_day":null,"loaded":null,"draught":null,"eta_month":3,"dte":true}"#),
+        ] {
+            let mut out = String::new();
+            let mut w = begin_record(&mut out, &header("", ""), 5, format);
+            w.opt_uint("eta_day", None);
+            w.opt_flag("loaded", None);
+            w.opt_decimal("draught", None);
+            w.opt_uint("eta_month", Some(3));
+            w.opt_flag("dte", Some(1));
+            w.end();
+            assert!(out.ends_with(expected), "{}", out);
+        }
+    }
 }
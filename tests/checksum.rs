@@ -0,0 +1,61 @@
+//! End-to-end checks of the `--checksum` modes, running the binary on
+//! sentences with good and bad checksums.
+
+use std::fs;
+use std::path::PathBuf;
+use std::process::Command;
+
+const GOOD: &str = "!AIVDM,1,1,,A,13u?etPv2;0n:dDPwUM1U1Cb069D,0*24";
+const BAD_SENTENCE: &str = "!AIVDM,1,1,,A,13u?etPv2;0n:dDPwUM1U1Cb069D,0*25";
+const BAD_TAG_BLOCK: &str = "\\s:sat1,c:1643588420*00\\!AIVDM,1,1,,A,13u?etPv2;0n:dDPwUM1U1Cb069D,0*24";
+const LOWERCASE: &str = "!AIVDM,1,1,,B,B52K>;h00Fc>jpUlNV@ikwpUoP06,0*4f";
+
+/// Run the parser on `input` in checksum `mode` with a reject file; returns
+/// the output and reject file lines
+fn run(mode: &str, input: &str) -> (Vec<String>, Vec<String>) {
+    let dir = std::env::temp_dir();
+    let path = |ext: &str| -> PathBuf { dir.join(format!("rustaise-checksum-{}-{}.{}", mode, std::process::id(), ext)) };
+    let (in_path, out_path, reject_path) = (path("nmea"), path("json"), path("rejects.json"));
+    fs::write(&in_path, input).unwrap();
+    let status = Command::new(env!("CARGO_BIN_EXE_rustaise"))
+        .arg(&in_path)
+        .arg(&out_path)
+        .args(["500000", "1", "--checksum", mode, "--reject-file"])
+        .arg(&reject_path)
+        .output()
+        .unwrap()
+        .status;
+    assert!(status.success());
+    let lines = |path: &PathBuf| -> Vec<String> {
+        let text = fs::read_to_string(path).unwrap_or_default();
+        let _ = fs::remove_file(path);
+        text.lines().map(str::to_string).collect()
+    };
+    fs::remove_file(&in_path).unwrap();
+    (lines(&out_path), lines(&reject_path))
+}
+
+fn input() -> String {
+    [GOOD, BAD_SENTENCE, BAD_TAG_BLOCK, LOWERCASE].join("\n") + "\n"
+}
+
+#[test]
+fn flag_mode_marks_bad_checksums() {
+    let (lines, rejects) = run("flag", &input());
+    let flags: Vec<bool> = lines.iter().map(|line| !line.contains("\"checksum_ok\":false")).collect();
+    assert_eq!(flags, [true, false, false, true], "{:#?}", lines);
+    assert!(lines.iter().all(|line| line.contains("\"checksum_ok\":")));
+    assert!(rejects.is_empty(), "{:#?}", rejects);
+}
+
+#[test]
+fn reject_mode_writes_bad_lines_to_the_reject_file() {
+    let (lines, rejects) = run("reject", &input());
+    assert_eq!(lines.len(), 2, "{:#?}", lines);
+    assert!(lines.iter().all(|line| !line.contains("checksum_ok")));
+    let expected: Vec<String> = [BAD_SENTENCE, BAD_TAG_BLOCK]
+        .iter()
+        .map(|line| format!("{{\"reason\":\"checksum\",\"sentence\":\"{}\"}}", line.replace('\\', "\\\\")))
+        .collect();
+    assert_eq!(rejects, expected);
+}
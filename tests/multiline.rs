@@ -0,0 +1,215 @@
+//! End-to-end checks of multi-part message assembly, running the binary on
+//! generated input.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use std::process::Command;
+
+/// Armor `(width, value)` fields into an AIS payload; returns it with its fill bits
+fn armor(fields: &[(usize, u64)]) -> (String, usize) {
+    let mut bits: Vec<u8> = Vec::new();
+    for &(width, value) in fields {
+        bits.extend((0..width).rev().map(|i| ((value >> i) & 1) as u8));
+    }
+    let fill = (6 - bits.len() % 6) % 6;
+    bits.resize(bits.len() + fill, 0);
+    let payload = bits
+        .chunks(6)
+        .map(|chunk| {
+            let v = chunk.iter().fold(0, |acc, &b| acc << 1 | b);
+            (if v < 40 { v + 48 } else { v + 56 }) as char
+        })
+        .collect();
+    (payload, fill)
+}
+
+/// 6-bit text fields of `chars` characters, padded with '@'
+fn text(s: &str, chars: usize) -> Vec<(usize, u64)> {
+    let padded = format!("{:@<width$}", s, width = chars);
+    padded.bytes().map(|c| (6, (if c >= 64 { c - 64 } else { c }) as u64)).collect()
+}
+
+/// A 424-bit type 5 static report whose name and destination (which spans
+/// both sentences) are unique to `mmsi`
+fn type5(mmsi: u64) -> (String, usize) {
+    let mut fields = vec![(6, 5), (2, 0), (30, mmsi), (2, 0), (30, 9_000_000 + mmsi % 1_000_000)];
+    fields.extend(text("CALL", 7));
+    fields.extend(text(&format!("SHIP {}", mmsi), 20));
+    fields.extend([(8, 70), (9, 100), (9, 20), (6, 5), (6, 6), (4, 1), (4, 5), (5, 15), (5, 12), (6, 30), (8, 55)]);
+    fields.extend(text(&format!("{:>20}", mmsi), 20));
+    fields.extend([(1, 0), (1, 0)]);
+    armor(&fields)
+}
+
+fn checksum(body: &str) -> u8 {
+    body.bytes().fold(0, |acc, c| acc ^ c)
+}
+
+fn sentence(count: usize, number: usize, id: &str, channel: &str, payload: &str, fill: usize) -> String {
+    let body = format!("AIVDM,{},{},{},{},{},{}", count, number, id, channel, payload, fill);
+    format!("!{}*{:02X}", body, checksum(&body))
+}
+
+fn tag_block(fields: &str) -> String {
+    format!("\\{}*{:02X}\\", fields, checksum(fields))
+}
+
+/// Run the parser on `input` with `threads` parse threads and return the output lines
+fn run(name: &str, input: &str, threads: usize) -> Vec<String> {
+    let dir = std::env::temp_dir();
+    let in_path: PathBuf = dir.join(format!("rustaise-{}-{}.nmea", name, std::process::id()));
+    let out_path: PathBuf = dir.join(format!("rustaise-{}-{}.json", name, std::process::id()));
+    fs::write(&in_path, input).unwrap();
+    let status = Command::new(env!("CARGO_BIN_EXE_rustaise"))
+        .arg(&in_path)
+        .arg(&out_path)
+        .arg("500000")
+        .arg(threads.to_string())
+        .output()
+        .unwrap()
+        .status;
+    assert!(status.success());
+    let output = fs::read_to_string(&out_path).unwrap();
+    fs::remove_file(in_path).unwrap();
+    fs::remove_file(out_path).unwrap();
+    output.lines().map(str::to_string).collect()
+}
+
+/// The quoted string value of `key` in a JSON line
+fn field<'a>(line: &'a str, key: &str) -> &'a str {
+    let start = line.find(&format!("\"{}\":\"", key)).unwrap() + key.len() + 4;
+    &line[start..start + line[start..].find('"').unwrap()]
+}
+
+#[test]
+fn plain_nmea_pairs_are_not_cross_stitched_across_parse_threads() {
+    // Sequential message IDs cycle through 0-9 on each channel, so only the
+    // order of the input tells the pairs apart
+    let mut input = String::new();
+    let mut expected = HashMap::new();
+    for i in 0..50_000u64 {
+        let mmsi = 200_000_000 + i;
+        let (payload, fill) = type5(mmsi);
+        let (id, channel) = ((i % 10).to_string(), if i % 20 < 10 { "A" } else { "B" });
+        input += &sentence(2, 1, &id, channel, &payload[..60], 0);
+        input += "\n";
+        input += &sentence(2, 2, &id, channel, &payload[60..], fill);
+        input += "\n";
+        expected.insert(mmsi.to_string(), payload);
+    }
+
+    let lines = run("pairs", &input, 4);
+    assert_eq!(lines.len(), expected.len());
+    for line in &lines {
+        assert_eq!(field(line, "raw_payload"), expected[field(line, "mmsi")], "{}", line);
+    }
+}
+
+#[test]
+fn tag_block_groups_take_the_source_from_their_first_sentence() {
+    // Only the first sentence of each group carries s: and c:
+    let mut input = String::new();
+    for i in 0..100u64 {
+        let (payload, fill) = type5(300_000_000 + i);
+        let first = tag_block(&format!("g:1-2-{},s:sat{},c:1643588400", 1000 + i, i % 3));
+        input += &format!("{}{}\n", first, sentence(2, 1, "1", "A", &payload[..60], 0));
+        let second = tag_block(&format!("g:2-2-{}", 1000 + i));
+        input += &format!("{}{}\n", second, sentence(2, 2, "1", "A", &payload[60..], fill));
+    }
+
+    let lines = run("groups", &input, 2);
+    assert_eq!(lines.len(), 100);
+    for line in &lines {
+        let i = field(line, "mmsi").parse::<u64>().unwrap() - 300_000_000;
+        assert_eq!(field(line, "source"), format!("sat{}", i % 3));
+        assert_eq!(field(line, "satellite_acquisition_time"), "1643588400");
+    }
+}
+
+#[test]
+fn interleaved_tag_block_groups_from_two_sources_are_not_cross_stitched() {
+    // Two satellites relay different messages under the same group ID, with
+    // their sentences interleaved
+    for tagged_throughout in [true, false] {
+        let mut input = String::new();
+        let mut expected = HashMap::new();
+        for i in 0..100u64 {
+            let group = 1000 + i % 10;
+            let parts: Vec<_> = (1..=2u64).map(|sat| (sat, type5(600_000_000 + i * 10 + sat))).collect();
+            for number in 1..=2 {
+                for (sat, (payload, fill)) in &parts {
+                    let tags = match (number, tagged_throughout) {
+                        (1, _) => format!("g:1-2-{},s:sat{},c:1643588400", group, sat),
+                        (_, true) => format!("g:2-2-{},s:sat{}", group, sat),
+                        (_, false) => format!("g:2-2-{}", group),
+                    };
+                    let (chunk, fill) = if number == 1 { (&payload[..60], 0) } else { (&payload[60..], *fill) };
+                    input += &format!("{}{}\n", tag_block(&tags), sentence(2, number, "1", "A", chunk, fill));
+                }
+            }
+            for (sat, (payload, _)) in parts {
+                expected.insert((600_000_000 + i * 10 + sat).to_string(), (format!("sat{}", sat), payload));
+            }
+        }
+
+        let lines = run("interleaved", &input, 2);
+        // Without a source on the second sentences, which group they belong to
+        // is ambiguous and they are rejected
+        assert_eq!(lines.len(), if tagged_throughout { 200 } else { 0 });
+        for line in &lines {
+            let (source, payload) = &expected[field(line, "mmsi")];
+            assert_eq!(field(line, "source"), source, "{}", line);
+            assert_eq!(field(line, "raw_payload"), payload, "{}", line);
+        }
+    }
+}
+
+/// `payload` split into `count` sentences with message ID `id` on channel A
+fn sentences(count: usize, id: &str, payload: &str, fill: usize) -> Vec<String> {
+    let size = payload.len().div_ceil(count);
+    let chunks: Vec<&str> = payload.as_bytes().chunks(size).map(|c| std::str::from_utf8(c).unwrap()).collect();
+    assert_eq!(chunks.len(), count);
+    chunks
+        .iter()
+        .enumerate()
+        .map(|(i, chunk)| sentence(count, i + 1, id, "A", chunk, if i + 1 == count { fill } else { 0 }))
+        .collect()
+}
+
+#[test]
+fn messages_spanning_many_parse_runs_are_assembled() {
+    // Over a megabyte of other traffic between sentences puts each sentence
+    // of a message in a different run of the input
+    let padding = "$GPTXT,01,01,02,PADDING*00\n".repeat(45_000);
+    for count in [3, 9] {
+        let (payload, fill) = type5(400_000_000 + count as u64);
+        for gap in ["", padding.as_str()] {
+            let input = sentences(count, "1", &payload, fill).join(&format!("\n{}", gap)) + "\n";
+            for threads in [1, 2, 4] {
+                let lines = run("spanning", &input, threads);
+                assert_eq!(lines.len(), 1, "{} sentences, {} threads", count, threads);
+                assert_eq!(field(&lines[0], "raw_payload"), payload);
+                assert_eq!(field(&lines[0], "mmsi"), (400_000_000 + count as u64).to_string());
+            }
+        }
+    }
+}
+
+#[test]
+fn a_message_id_reused_in_a_later_parse_run_supersedes_the_open_message() {
+    // The first message never completes; its ID is reused by a complete
+    // message a run later, and a stray second sentence follows a run after that
+    let padding = "$GPTXT,01,01,02,PADDING*00\n".repeat(45_000);
+    let (first, _) = type5(500_000_001);
+    let (second, fill) = type5(500_000_002);
+    let (third, third_fill) = type5(500_000_003);
+    let mut input = sentences(2, "1", &first, 0)[0].clone() + "\n" + &padding;
+    input += &(sentences(2, "1", &second, fill).join("\n") + "\n" + &padding);
+    input += &(sentences(2, "1", &third, third_fill)[1].clone() + "\n");
+    for threads in [1, 4] {
+        let lines = run("reused", &input, threads);
+        assert_eq!(lines.len(), 1, "{} threads: {:?}", threads, lines);
+        assert_eq!(field(&lines[0], "raw_payload"), second);
+    }
+}